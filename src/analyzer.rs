@@ -1,145 +1,308 @@
 use crate::error::{Error, ErrorKind, Result};
 use crate::lexer::token::Token;
 use crate::parser::ast::*;
+use crate::parser::visit::*;
+use crate::position::token_position::TokenSpan;
 use crate::symbol_table::SymbolTable;
 use crate::types::Type;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 
+/// What the analyzer knows about a variable at a point of the program
+#[derive(Copy, Clone, Debug)]
+pub struct Symbol {
+  pub typ: Type,
+  /// Whether the variable is assigned on every path reaching this point
+  pub initialized: bool,
+  pub mutability: Mutability,
+  /// Index of the declared identifier's token, `None` if the
+  /// variable was declared in another token stream
+  pub token: Option<usize>,
+}
+
+/// Type of every node of an analyzed program. Caravel has no
+/// implicit conversions, and variables are resolved in
+/// `resolver::Resolution`
+#[derive(Clone, Debug, Default)]
+pub struct Types(HashMap<usize, Type>);
+
+impl Types {
+  pub fn of<T>(&self, id: NodeId<T>) -> Option<Type> {
+    self.0.get(&id.index()).copied()
+  }
+
+  /// Records the type of a node, e.g. one added to the tree after analysis
+  pub fn set<T>(&mut self, id: NodeId<T>, typ: Type) {
+    self.0.insert(id.index(), typ);
+  }
+}
+
 pub struct Analyzer<'a> {
-  symbol_table: SymbolTable,
+  symbol_table: SymbolTable<Symbol>,
   tokens: &'a Vec<Token>,
+  ast: &'a Ast,
+  types: Types,
+  /// First error found. Visiting goes on after it, but
+  /// nodes depending on a node that failed are left untyped
+  failure: Option<Error>,
 }
 
 impl<'a> Analyzer<'a> {
-  pub fn new(tokens: &'a Vec<Token>) -> Self {
+  pub fn new(tokens: &'a Vec<Token>, ast: &'a Ast) -> Self {
+    Self::with_symbol_table(tokens, ast, SymbolTable::new(None))
+  }
+
+  /// Analyzes against previously declared symbols,
+  /// e.g. those of earlier inputs in a REPL session
+  pub fn with_symbol_table(
+    tokens: &'a Vec<Token>,
+    ast: &'a Ast,
+    symbol_table: SymbolTable<Symbol>,
+  ) -> Self {
     Self {
-      symbol_table: SymbolTable::new(None),
+      symbol_table,
       tokens,
+      ast,
+      types: Types::default(),
+      failure: None,
     }
   }
 
-  pub fn analyze(&mut self, program: &Block) -> Result<Type> {
-    self.analyze_block(program)
+  /// Types of the nodes analyzed so far
+  pub fn into_types(self) -> Types {
+    self.types
+  }
+
+  /// Symbols declared so far, to analyze further token streams with
+  pub fn into_symbol_table(mut self) -> SymbolTable<Symbol> {
+    // Token indices are meaningless outside of this token stream
+    self.symbol_table.for_each_mut(|symbol| symbol.token = None);
+    self.symbol_table
   }
 
-  fn analyze_expression(&mut self, expr: &Expression) -> Result<Type> {
-    match expr {
-      Expression::Assignment(assig) => self.analyze_assignment(assig),
-      Expression::Block(block) => self.analyze_block(block),
-      Expression::Term(term) => self.analyze_term(term),
+  pub fn analyze(&mut self) -> Result<Type> {
+    let ast = self.ast;
+    self.visit_block(ast, ast.root());
+    match self.failure.take() {
+      Some(error) => Err(error),
+      None => Ok(self.types.of(ast.root()).unwrap()),
     }
   }
 
-  fn analyze_block(&mut self, block: &Block) -> Result<Type> {
-    for (i, expr) in block.expressions.iter().enumerate() {
-      let expr_type = self.analyze_expression(expr);
-      if i == block.expressions.len() - 1 {
-        return expr_type;
+  /// Records the type of a node, or the error it failed with.
+  /// `None` means that one of its children failed
+  fn record<T>(&mut self, id: NodeId<T>, typ: Option<Result<Type>>) {
+    match typ {
+      Some(Ok(typ)) => self.types.set(id, typ),
+      Some(Err(error)) => {
+        self.failure.get_or_insert(error);
       }
+      None => (),
     }
-    // Void type if no expressions in block
-    Ok(Type::Void)
-  }
-
-  fn analyze_assignment(&mut self, assig: &Assignment) -> Result<Type> {
-    match assig {
-      Assignment::Initialization(ident, type_ident, val, _) => {
-        if self.symbol_table.has(&ident) {
-          return Err(Error::new(
-            ErrorKind::Redeclaration,
-            &format!(r#"declaration of previously declared variable "{}""#, ident),
-            assig.pos().as_source_span(self.tokens),
-          ));
-        };
-        let typ = Type::try_from(type_ident.to_owned()).unwrap();
-        if let Some(val) = val {
-          let val_type = self.analyze_expression(val)?;
-          if val_type != typ {
-            return Err(Error::new(
-              ErrorKind::TypeMismatch,
-              "type mismatch",
-              assig.pos().as_source_span(self.tokens),
-            ));
-          }
-        };
-        self.symbol_table.set(&ident, typ);
-        Ok(typ)
+  }
+
+  fn error<T>(&self, kind: ErrorKind, msg: &str, id: NodeId<T>) -> Error {
+    Error::new(kind, msg, self.ast.span(id).as_source_span(self.tokens))
+  }
+
+  /// Type of a binary operation, whose operands must have the same type
+  fn operation<T, L, R>(
+    &self,
+    id: NodeId<T>,
+    lhs: NodeId<L>,
+    rhs: NodeId<R>,
+  ) -> Option<Result<Type>> {
+    let (lhs, rhs) = (self.types.of(lhs)?, self.types.of(rhs)?);
+    Some(match lhs == rhs {
+      true => Ok(lhs),
+      false => Err(self.error(ErrorKind::TypeMismatch, "type mismatch", id)),
+    })
+  }
+
+  fn initialization(
+    &mut self,
+    ast: &Ast,
+    id: NodeId<Assignment>,
+    mutability: Mutability,
+    ident: &str,
+    type_ident: &str,
+    val: Option<NodeId<Expression>>,
+  ) -> Option<Result<Type>> {
+    let ident_token = ast.ident_token(id);
+
+    if self.symbol_table.has(ident) {
+      return Some(Err(self.error(
+        ErrorKind::Redeclaration,
+        &format!(r#"declaration of previously declared variable "{}""#, ident),
+        id,
+      )));
+    };
+    let typ = match Type::try_from(type_ident.to_owned()) {
+      Ok(typ) => typ,
+      Err(_) => {
+        return Some(Err(Error::new(
+          ErrorKind::UnknownType,
+          &format!(r#"unknown type "{}""#, type_ident),
+          TokenSpan::new(ident_token + 2, 1).as_source_span(self.tokens),
+        )))
       }
-      Assignment::Reassignment(ident, val) => {
-        let typ = self.analyze_expression(val)?;
-        match self.symbol_table.get(&ident) {
-          None => {
-            return Err(Error::new(
-              ErrorKind::UndeclaredVariable,
-              &format!(r#"use of undeclared variable "{}""#, ident),
-              assig.pos().as_source_span(self.tokens),
-            ))
-          }
-          Some(cur_type) => {
-            if cur_type != typ {
-              return Err(Error::new(
-                ErrorKind::TypeMismatch,
-                "type mismatch",
-                assig.pos().as_source_span(self.tokens),
-              ));
-            }
-          }
-        }
-        Ok(typ)
+    };
+    walk_assignment(self, ast, id);
+    if let Some(val) = val {
+      if self.types.of(val)? != typ {
+        return Some(Err(self.error(
+          ErrorKind::TypeMismatch,
+          "type mismatch",
+          id,
+        )));
       }
-    }
+    };
+    self.symbol_table.set(
+      ident,
+      Symbol {
+        typ,
+        initialized: val.is_some(),
+        mutability,
+        token: Some(ident_token),
+      },
+    );
+    Some(Ok(typ))
   }
 
-  fn analyze_term(&self, term: &Term) -> Result<Type> {
-    match term {
-      Term::Factor(factor) => self.analyze_factor(factor),
-      Term::Operation(lhs, _, rhs) => {
-        let lhs_type = self.analyze_term(lhs)?;
-        let rhs_type = self.analyze_factor(rhs)?;
-        if lhs_type != rhs_type {
-          return Err(Error::new(
-            ErrorKind::TypeMismatch,
-            "type mismatch",
-            term.pos().as_source_span(self.tokens),
-          ));
-        }
-        Ok(lhs_type)
+  fn reassignment(
+    &mut self,
+    ast: &Ast,
+    id: NodeId<Assignment>,
+    ident: &str,
+    val: NodeId<Expression>,
+  ) -> Option<Result<Type>> {
+    walk_assignment(self, ast, id);
+    let typ = self.types.of(val)?;
+    let span = ast.span(id).as_source_span(self.tokens);
+    let symbol = match self.symbol_table.get_mut(ident) {
+      Some(symbol) => symbol,
+      None => {
+        return Some(Err(Error::new(
+          ErrorKind::UndeclaredVariable,
+          &format!(r#"use of undeclared variable "{}""#, ident),
+          span,
+        )))
+      }
+    };
+    if symbol.typ != typ {
+      return Some(Err(Error::new(
+        ErrorKind::TypeMismatch,
+        "type mismatch",
+        span,
+      )));
+    }
+    if symbol.initialized && symbol.mutability == Mutability::Immutable {
+      let mut error = Error::new(
+        ErrorKind::ImmutableAssignment,
+        &format!(r#"cannot reassign immutable variable "{}""#, ident),
+        span,
+      );
+      if let Some(token) = symbol.token {
+        error = error.with_note(
+          self.tokens[token].pos.clone(),
+          &format!(r#""{}" is declared here"#, ident),
+        );
       }
+      return Some(Err(error.with_help(&format!(
+        "consider making this binding mutable: `let mut {}`",
+        ident
+      ))));
     }
+    symbol.initialized = true;
+    Some(Ok(typ))
   }
 
-  fn analyze_factor(&self, factor: &Factor) -> Result<Type> {
-    match factor {
-      Factor::Leaf(leaf) => self.analyze_leaf(leaf),
-      Factor::Operation(lhs, _, rhs) => {
-        let lhs_type = self.analyze_factor(lhs)?;
-        let rhs_type = self.analyze_leaf(rhs)?;
-        if lhs_type != rhs_type {
-          return Err(Error::new(
-            ErrorKind::TypeMismatch,
-            "type mismatch",
-            factor.pos().as_source_span(self.tokens),
-          ));
-        }
-        Ok(lhs_type)
-      }
+  fn variable(&self, ident: &str, id: NodeId<Leaf>) -> Result<Type> {
+    match self.symbol_table.get(ident) {
+      Some(Symbol {
+        typ,
+        initialized: true,
+        ..
+      }) => Ok(typ),
+      Some(_) => Err(self.error(
+        ErrorKind::UninitializedVariable,
+        &format!(r#"use of possibly uninitialized variable "{}""#, ident),
+        id,
+      )),
+      None => Err(self.error(
+        ErrorKind::UndeclaredVariable,
+        &format!(r#"use of undeclared variable "{}""#, ident),
+        id,
+      )),
     }
   }
+}
 
-  fn analyze_leaf(&self, leaf: &Leaf) -> Result<Type> {
-    Ok(match leaf {
-      Leaf::FloatLiteral(_, _) => Type::Float,
-      Leaf::Identifier(ident, _) => match self.symbol_table.get(&ident) {
-        Some(typ) => typ,
-        None => {
-          return Err(Error::new(
-            ErrorKind::UndeclaredVariable,
-            &format!(r#"use of undeclared variable "{}""#, ident),
-            leaf.pos().as_source_span(self.tokens),
-          ));
-        }
-      },
-      Leaf::Term(term) => self.analyze_term(term)?,
-    })
+// Each node is typed once its children are
+impl<'a> Visitor for Analyzer<'a> {
+  fn visit_block(&mut self, ast: &Ast, id: NodeId<Block>) {
+    walk_block(self, ast, id);
+    // Void type if no expressions in block
+    let typ = match ast[id].expressions.last() {
+      Some(&expr) => self.types.of(expr),
+      None => Some(Type::Void),
+    };
+    self.record(id, typ.map(Ok));
+  }
+
+  fn visit_expression(&mut self, ast: &Ast, id: NodeId<Expression>) {
+    let typ = match ast[id] {
+      Expression::Assignment(assig) => {
+        self.visit_assignment(ast, assig);
+        self.types.of(assig)
+      }
+      Expression::Block(block) => {
+        self.visit_block(ast, block);
+        self.types.of(block)
+      }
+      Expression::Term(term) => {
+        self.visit_term(ast, term);
+        self.types.of(term)
+      }
+    };
+    self.record(id, typ.map(Ok));
+  }
+
+  fn visit_assignment(&mut self, ast: &Ast, id: NodeId<Assignment>) {
+    let typ = match &ast[id] {
+      Assignment::Initialization(mutability, ident, type_ident, val) => {
+        self.initialization(ast, id, *mutability, ident, type_ident, *val)
+      }
+      Assignment::Reassignment(ident, val) => self.reassignment(ast, id, ident, *val),
+    };
+    self.record(id, typ);
+  }
+
+  fn visit_term(&mut self, ast: &Ast, id: NodeId<Term>) {
+    walk_term(self, ast, id);
+    let typ = match ast[id] {
+      Term::Factor(factor) => self.types.of(factor).map(Ok),
+      Term::Operation(lhs, _, rhs) => self.operation(id, lhs, rhs),
+    };
+    self.record(id, typ);
+  }
+
+  fn visit_factor(&mut self, ast: &Ast, id: NodeId<Factor>) {
+    walk_factor(self, ast, id);
+    let typ = match ast[id] {
+      Factor::Leaf(leaf) => self.types.of(leaf).map(Ok),
+      Factor::Operation(lhs, _, rhs) => self.operation(id, lhs, rhs),
+    };
+    self.record(id, typ);
+  }
+
+  fn visit_leaf(&mut self, ast: &Ast, id: NodeId<Leaf>) {
+    walk_leaf(self, ast, id);
+    let typ = match &ast[id] {
+      Leaf::FloatLiteral(_) => Some(Ok(Type::Float)),
+      Leaf::Identifier(ident) => Some(self.variable(ident, id)),
+      Leaf::Term(term) => self.types.of(*term).map(Ok),
+    };
+    self.record(id, typ);
   }
 }
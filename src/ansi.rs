@@ -1,7 +1,133 @@
-pub const RESET: &'static str = "\x1b[0m";
-pub const BOLD: &'static str = "\x1b[1m";
-pub const RED: &'static str = "\x1b[31m";
+use std::env;
+use std::io::{self, IsTerminal};
 
-pub fn style(str: &str, escape_code: &'static str) -> String {
-  format!("{}{}{}", escape_code, str, RESET)
+pub const RESET: &str = "\x1b[0m";
+pub const BOLD: &str = "\x1b[1m";
+pub const DIM: &str = "\x1b[2m";
+pub const RED: &str = "\x1b[31m";
+pub const YELLOW: &str = "\x1b[33m";
+
+/// Whether output should contain escape codes
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ColorMode {
+  /// Color only when writing to a terminal, honoring
+  /// `NO_COLOR` and `CLICOLOR_FORCE`
+  Auto,
+  Always,
+  Never,
+}
+
+/// The output stream a `ColorMode` is resolved against
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Stream {
+  Stdout,
+  Stderr,
+}
+
+impl ColorMode {
+  pub fn parse(str: &str) -> Option<Self> {
+    match str {
+      "auto" => Some(Self::Auto),
+      "always" => Some(Self::Always),
+      "never" => Some(Self::Never),
+      _ => None,
+    }
+  }
+
+  /// Decides whether text written to `stream` should be colored
+  pub fn enabled(self, stream: Stream) -> bool {
+    let terminal = match stream {
+      Stream::Stdout => io::stdout().is_terminal(),
+      Stream::Stderr => io::stderr().is_terminal(),
+    };
+    self.enabled_with(terminal, |name| env::var(name).ok())
+  }
+
+  /// `enabled`, with the terminal check and the environment given
+  fn enabled_with(self, terminal: bool, var: impl Fn(&str) -> Option<String>) -> bool {
+    // Unset and empty variables are treated alike
+    let set = |name| var(name).filter(|value| !value.is_empty());
+    match self {
+      Self::Always => true,
+      Self::Never => false,
+      Self::Auto => {
+        // https://no-color.org: any non-empty value disables color
+        if set("NO_COLOR").is_some() {
+          return false;
+        }
+        if set("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+          return true;
+        }
+        terminal
+      }
+    }
+  }
+}
+
+/// Wraps `str` in `escape_code`, or returns it unchanged if `color` is false
+pub fn style(str: &str, escape_code: &'static str, color: bool) -> String {
+  match color {
+    true => format!("{}{}{}", escape_code, str, RESET),
+    false => str.to_owned(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+    move |name| {
+      vars
+        .iter()
+        .find(|(var, _)| *var == name)
+        .map(|(_, value)| value.to_string())
+    }
+  }
+
+  #[test]
+  fn always_and_never_ignore_the_environment() {
+    let vars = [("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")];
+    for terminal in [false, true] {
+      assert!(ColorMode::Always.enabled_with(terminal, env(&vars)));
+      assert!(!ColorMode::Never.enabled_with(terminal, env(&vars)));
+    }
+  }
+
+  #[test]
+  fn auto_colors_terminals_only() {
+    assert!(ColorMode::Auto.enabled_with(true, env(&[])));
+    assert!(!ColorMode::Auto.enabled_with(false, env(&[])));
+  }
+
+  #[test]
+  fn auto_honors_no_color() {
+    assert!(!ColorMode::Auto.enabled_with(true, env(&[("NO_COLOR", "1")])));
+    // It wins over CLICOLOR_FORCE
+    let vars = [("NO_COLOR", "yes"), ("CLICOLOR_FORCE", "1")];
+    assert!(!ColorMode::Auto.enabled_with(false, env(&vars)));
+    // An empty value counts as unset
+    assert!(ColorMode::Auto.enabled_with(true, env(&[("NO_COLOR", "")])));
+  }
+
+  #[test]
+  fn auto_honors_clicolor_force() {
+    assert!(ColorMode::Auto.enabled_with(false, env(&[("CLICOLOR_FORCE", "1")])));
+    assert!(!ColorMode::Auto.enabled_with(false, env(&[("CLICOLOR_FORCE", "0")])));
+    assert!(!ColorMode::Auto.enabled_with(false, env(&[("CLICOLOR_FORCE", "")])));
+  }
+
+  #[test]
+  fn parses_modes() {
+    assert_eq!(ColorMode::parse("auto"), Some(ColorMode::Auto));
+    assert_eq!(ColorMode::parse("always"), Some(ColorMode::Always));
+    assert_eq!(ColorMode::parse("never"), Some(ColorMode::Never));
+    assert_eq!(ColorMode::parse("yes"), None);
+  }
+
+  #[test]
+  fn styles_only_with_color() {
+    assert_eq!(style("text", RED, true), "\x1b[31mtext\x1b[0m");
+    assert_eq!(style("text", RED, false), "text");
+  }
 }
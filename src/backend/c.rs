@@ -0,0 +1,146 @@
+use crate::interpreter::parse_float;
+use crate::parser::ast::*;
+use crate::resolver::{Resolution, SymbolId};
+use std::fmt::Write;
+
+/// Emits a C99 program whose `main` prints the value of the program
+/// with enough digits to read it back exactly. Every variable becomes
+/// a `double` local, and blocks are flattened into statements
+pub fn emit(ast: &Ast, resolution: &Resolution) -> String {
+  let mut emitter = Emitter {
+    ast,
+    resolution,
+    statements: Vec::new(),
+  };
+  let result = emitter.block(ast.root());
+
+  let mut out = String::from("#include <math.h>\n#include <stdio.h>\n\nint main(void) {\n");
+  for (i, declaration) in resolution.declarations.iter().enumerate() {
+    if declaration.type_ident != "void" {
+      writeln!(out, "  double {};", var_name(resolution, SymbolId(i))).unwrap();
+    }
+  }
+  for statement in &emitter.statements {
+    writeln!(out, "  {}", statement).unwrap();
+  }
+  if let Some(result) = result {
+    writeln!(out, "  printf(\"%.17g\\n\", {});", result).unwrap();
+  }
+  out += "  return 0;\n}\n";
+  out
+}
+
+struct Emitter<'a> {
+  ast: &'a Ast,
+  resolution: &'a Resolution,
+  statements: Vec<String>,
+}
+
+impl<'a> Emitter<'a> {
+  // Each function returns a side-effect-free C expression for the
+  // value, after emitting the statements needed to compute it,
+  // or `None` for `void`
+
+  fn block(&mut self, block: NodeId<Block>) -> Option<String> {
+    let mut value = None;
+    for &expr in &self.ast[block].expressions {
+      value = self.expression(expr);
+    }
+    value
+  }
+
+  fn expression(&mut self, expr: NodeId<Expression>) -> Option<String> {
+    match self.ast[expr] {
+      Expression::Assignment(assig) => self.assignment(assig),
+      Expression::Block(block) => self.block(block),
+      Expression::Term(term) => self.term(term),
+    }
+  }
+
+  fn assignment(&mut self, assig: NodeId<Assignment>) -> Option<String> {
+    let val = match self.ast[assig] {
+      Assignment::Initialization(_, _, _, None) => return None,
+      Assignment::Initialization(_, _, _, Some(val)) | Assignment::Reassignment(_, val) => {
+        self.expression(val)?
+      }
+    };
+
+    // The analyzer has checked that every assigned variable is declared
+    let id = self
+      .resolution
+      .symbol_at(self.ast.ident_token(assig))
+      .unwrap();
+    let var = var_name(self.resolution, id);
+    self.statements.push(format!("{} = {};", var, val));
+    Some(var)
+  }
+
+  fn term(&mut self, term: NodeId<Term>) -> Option<String> {
+    match self.ast[term] {
+      Term::Factor(factor) => self.factor(factor),
+      Term::Operation(lhs, op, rhs) => {
+        // Operands are never blocks or assignments, so they don't
+        // emit statements that could change each other's values
+        let (lhs, rhs) = (self.term(lhs)?, self.factor(rhs)?);
+        Some(match op {
+          TermOp::Add => format!("({} + {})", lhs, rhs),
+          TermOp::Subtract => format!("({} - {})", lhs, rhs),
+        })
+      }
+    }
+  }
+
+  fn factor(&mut self, factor: NodeId<Factor>) -> Option<String> {
+    match self.ast[factor] {
+      Factor::Leaf(leaf) => self.leaf(leaf),
+      Factor::Operation(lhs, op, rhs) => {
+        let (lhs, rhs) = (self.factor(lhs)?, self.leaf(rhs)?);
+        Some(match op {
+          FactorOp::Multiply => format!("({} * {})", lhs, rhs),
+          FactorOp::Divide => format!("({} / {})", lhs, rhs),
+          FactorOp::Modulo => format!("fmod({}, {})", lhs, rhs),
+        })
+      }
+    }
+  }
+
+  fn leaf(&mut self, leaf: NodeId<Leaf>) -> Option<String> {
+    match &self.ast[leaf] {
+      Leaf::FloatLiteral(value) => Some(float(parse_float(value))),
+      Leaf::Identifier(_) => {
+        let start = self.ast.span(leaf).start;
+        let id = self.resolution.uses[&start];
+        if self.resolution.declaration(id).type_ident == "void" {
+          return None;
+        }
+        Some(var_name(self.resolution, id))
+      }
+      Leaf::Term(term) => self.term(*term),
+    }
+  }
+}
+
+/// Name of a variable in C. The symbol's index keeps names
+/// clear of C's keywords and library
+fn var_name(resolution: &Resolution, id: SymbolId) -> String {
+  let name = &resolution.declaration(id).name;
+  let valid = name
+    .chars()
+    .all(|char| char.is_ascii_alphanumeric() || char == '_');
+  if valid && !name.starts_with('_') {
+    format!("{}_{}", name, id.0)
+  } else {
+    // Other identifiers may be reserved, or unsupported by compilers
+    format!("var{}", id.0)
+  }
+}
+
+fn float(value: f64) -> String {
+  match value {
+    value if value.is_nan() => "NAN".to_owned(),
+    value if value == f64::INFINITY => "INFINITY".to_owned(),
+    value if value == f64::NEG_INFINITY => "-INFINITY".to_owned(),
+    // Debug formatting keeps the digits needed to round-trip
+    value => format!("{:?}", value),
+  }
+}
@@ -0,0 +1,60 @@
+use super::tree::Node;
+use crate::lexer::token::Token;
+use crate::parser::ast::Ast;
+use crate::position::token_position::TokenSpan;
+use std::fmt::Write;
+
+/// Renders the syntax tree as a Graphviz graph. Hovering
+/// over a node shows the part of the source it spans
+pub fn emit(ast: &Ast, tokens: &[Token]) -> String {
+  let mut out =
+    String::from("digraph ast {\n  ordering=out;\n  node [shape=box, fontname=\"monospace\"];\n");
+  let mut count = 0;
+  node(&mut out, &Node::new(ast, None), tokens, &mut count);
+  out += "}\n";
+  out
+}
+
+/// Writes `node` and its subtree, returning its ID
+fn node(out: &mut String, node: &Node, tokens: &[Token], count: &mut usize) -> usize {
+  let id = *count;
+  *count += 1;
+
+  let (label, span, shape) = match node {
+    Node::Leaf(label, span, _) => (label, span, ", shape=plaintext"),
+    Node::Tree(label, span, ..) => (label, span, ""),
+  };
+  writeln!(
+    out,
+    "  n{} [label=\"{}\", tooltip=\"{}\"{}];",
+    id,
+    escape(label),
+    escape(&tooltip(span, tokens)),
+    shape
+  )
+  .unwrap();
+
+  if let Node::Tree(.., children) = node {
+    for child in children {
+      let child = self::node(out, child, tokens, count);
+      writeln!(out, "  n{} -> n{};", id, child).unwrap();
+    }
+  }
+  id
+}
+
+/// Position of the span, with one-based lines and columns
+fn tooltip(span: &TokenSpan, tokens: &[Token]) -> String {
+  let source = span.as_source_span(tokens);
+  format!(
+    "{}:{}, tokens {}..{}",
+    source.start_pos.line + 1,
+    source.start_pos.column + 1,
+    span.start,
+    span.start + span.len
+  )
+}
+
+fn escape(str: &str) -> String {
+  str.replace('\\', "\\\\").replace('"', "\\\"")
+}
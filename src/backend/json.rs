@@ -0,0 +1,124 @@
+//! The syntax tree as JSON, for tools that don't link the compiler.
+//! The document is `{"version": 2, "program": <Block>}`, and every
+//! node has a `kind`, the `tokens` it spans, as `{"start", "len"}`
+//! indices into the token stream, and its `type` as found by the
+//! analyzer (`"float"`, `"void"`, or `null` if unknown). By kind, the
+//! other fields are:
+//!
+//! - `Block`: `expressions`
+//! - `Initialization`: `name`, `mutable`, `annotation` (the type as
+//!   written) and `value`, which is `null` without an initializer
+//! - `Reassignment`: `name` and `value`
+//! - `Binary`: `op` (`Add`, `Subtract`, `Multiply`, `Divide` or
+//!   `Modulo`), `lhs` and `rhs`
+//! - `Identifier`: `name`
+//! - `FloatLiteral`: `value`, as written
+//!
+//! Parentheses have no node of their own. Fields are only ever added
+//! within a version. Version 2 added `type`, and renamed the
+//! `type` of `Initialization` to `annotation`
+
+use crate::analyzer::Types;
+use crate::parser::ast::*;
+use serde_json::{json, Value};
+
+pub const VERSION: u64 = 2;
+
+pub fn emit(ast: &Ast, types: &Types) -> String {
+  let exporter = Exporter { ast, types };
+  let document = json!({ "version": VERSION, "program": exporter.block(ast.root()) });
+  serde_json::to_string_pretty(&document).unwrap() + "\n"
+}
+
+struct Exporter<'a> {
+  ast: &'a Ast,
+  types: &'a Types,
+}
+
+impl<'a> Exporter<'a> {
+  /// Node of `kind` for `id`, with the given fields
+  fn node<T>(&self, kind: &str, id: NodeId<T>, fields: Value) -> Value {
+    let span = self.ast.span(id);
+    let mut node = fields;
+    node["kind"] = json!(kind);
+    node["tokens"] = json!({ "start": span.start, "len": span.len });
+    node["type"] = json!(self.types.of(id).map(|typ| typ.to_string()));
+    node
+  }
+
+  fn block(&self, block: NodeId<Block>) -> Value {
+    let expressions: Vec<_> = self.ast[block]
+      .expressions
+      .iter()
+      .map(|&expr| self.expression(expr))
+      .collect();
+    self.node("Block", block, json!({ "expressions": expressions }))
+  }
+
+  fn expression(&self, expr: NodeId<Expression>) -> Value {
+    match self.ast[expr] {
+      Expression::Assignment(assig) => self.assignment(assig),
+      Expression::Block(block) => self.block(block),
+      Expression::Term(term) => self.term(term),
+    }
+  }
+
+  fn assignment(&self, assig: NodeId<Assignment>) -> Value {
+    match &self.ast[assig] {
+      Assignment::Initialization(mutability, name, typ, val) => self.node(
+        "Initialization",
+        assig,
+        json!({
+          "name": name,
+          "mutable": *mutability == Mutability::Mutable,
+          "annotation": typ,
+          "value": val.map(|val| self.expression(val)),
+        }),
+      ),
+      Assignment::Reassignment(name, val) => self.node(
+        "Reassignment",
+        assig,
+        json!({ "name": name, "value": self.expression(*val) }),
+      ),
+    }
+  }
+
+  fn term(&self, term: NodeId<Term>) -> Value {
+    match self.ast[term] {
+      Term::Factor(factor) => self.factor(factor),
+      Term::Operation(lhs, op, rhs) => {
+        let op = match op {
+          TermOp::Add => "Add",
+          TermOp::Subtract => "Subtract",
+        };
+        self.binary(term, op, self.term(lhs), self.factor(rhs))
+      }
+    }
+  }
+
+  fn factor(&self, factor: NodeId<Factor>) -> Value {
+    match self.ast[factor] {
+      Factor::Leaf(leaf) => self.leaf(leaf),
+      Factor::Operation(lhs, op, rhs) => {
+        let op = match op {
+          FactorOp::Multiply => "Multiply",
+          FactorOp::Divide => "Divide",
+          FactorOp::Modulo => "Modulo",
+        };
+        self.binary(factor, op, self.factor(lhs), self.leaf(rhs))
+      }
+    }
+  }
+
+  fn leaf(&self, leaf: NodeId<Leaf>) -> Value {
+    match &self.ast[leaf] {
+      Leaf::Identifier(name) => self.node("Identifier", leaf, json!({ "name": name })),
+      Leaf::FloatLiteral(value) => self.node("FloatLiteral", leaf, json!({ "value": value })),
+      Leaf::Term(term) => self.term(*term),
+    }
+  }
+
+  fn binary<T>(&self, id: NodeId<T>, op: &str, lhs: Value, rhs: Value) -> Value {
+    self.node("Binary", id, json!({ "op": op, "lhs": lhs, "rhs": rhs }))
+  }
+}
@@ -1 +1,8 @@
+pub mod c;
+pub mod dot;
+pub mod json;
 pub mod print;
+pub mod tokens;
+pub mod tree;
+pub mod wasm;
+pub mod x86_64;
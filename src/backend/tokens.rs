@@ -0,0 +1,77 @@
+//! Dumps of the token stream, one entry per token with its kind,
+//! its text and where it starts (one-based line and column, in
+//! characters) and its length in bytes. The brackets the lexer wraps
+//! every program in are included; they are empty and marked synthetic
+
+use crate::lexer::token::Token;
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+pub const VERSION: u64 = 1;
+
+/// One aligned line per token:
+///
+/// ```text
+/// LBracket ""    1:1 0 synthetic
+/// Let      "let" 1:1 3
+/// ```
+pub fn emit(tokens: &[Token], source: &str) -> String {
+  let rows: Vec<[String; 3]> = tokens
+    .iter()
+    .map(|token| {
+      [
+        token.kind.name().to_owned(),
+        format!("{:?}", lexeme(token, source)),
+        format!(
+          "{}:{}",
+          token.pos.start_pos.line + 1,
+          token.pos.start_pos.column + 1
+        ),
+      ]
+    })
+    .collect();
+  let width = |column: usize| rows.iter().map(|row| row[column].chars().count()).max();
+
+  let mut out = String::new();
+  for (row, token) in rows.iter().zip(tokens) {
+    let mut line = String::new();
+    for (column, cell) in row.iter().enumerate() {
+      write!(line, "{:<1$} ", cell, width(column).unwrap()).unwrap();
+    }
+    write!(line, "{}", token.pos.len).unwrap();
+    if synthetic(token) {
+      line += " synthetic";
+    }
+    writeln!(out, "{}", line).unwrap();
+  }
+  out
+}
+
+/// The same information as `emit`, as
+/// `{"version": 1, "tokens": [{"kind", "lexeme", "line", "column", "offset", "len", "synthetic"}]}`
+pub fn emit_json(tokens: &[Token], source: &str) -> String {
+  let tokens: Vec<Value> = tokens
+    .iter()
+    .map(|token| {
+      json!({
+        "kind": token.kind.name(),
+        "lexeme": lexeme(token, source),
+        "line": token.pos.start_pos.line + 1,
+        "column": token.pos.start_pos.column + 1,
+        "offset": token.pos.start_pos.offset,
+        "len": token.pos.len,
+        "synthetic": synthetic(token),
+      })
+    })
+    .collect();
+  serde_json::to_string_pretty(&json!({ "version": VERSION, "tokens": tokens })).unwrap() + "\n"
+}
+
+fn lexeme<'a>(token: &Token, source: &'a str) -> &'a str {
+  &source[token.pos.start_pos.offset..token.pos.end()]
+}
+
+/// Whether the lexer made up the token, rather than reading it
+fn synthetic(token: &Token) -> bool {
+  token.pos.len == 0
+}
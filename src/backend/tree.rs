@@ -0,0 +1,106 @@
+use crate::analyzer::Types;
+use crate::parser::ast::*;
+use crate::parser::visit::*;
+use crate::position::token_position::TokenSpan;
+use crate::types::Type;
+
+/// Simplified view of the syntax tree drawn by `print` and `dot`:
+/// a label per node, with the tokens it spans and its type, if known
+pub enum Node {
+  Leaf(String, TokenSpan, Option<Type>),
+  Tree(String, TokenSpan, Option<Type>, Vec<Node>),
+}
+
+impl Node {
+  /// View of `ast`, with the types of its nodes if given
+  pub fn new(ast: &Ast, types: Option<&Types>) -> Self {
+    let mut builder = Builder {
+      types,
+      nodes: Vec::new(),
+    };
+    builder.visit_block(ast, ast.root());
+    builder.nodes.pop().unwrap()
+  }
+}
+
+/// Visits the tree, pushing a view of each node visited once its
+/// children's views are pushed, which it then takes as its children
+struct Builder<'a> {
+  types: Option<&'a Types>,
+  nodes: Vec<Node>,
+}
+
+impl<'a> Builder<'a> {
+  /// Makes a view of the nodes pushed since there were `start`
+  fn tree<T>(&mut self, ast: &Ast, label: &str, id: NodeId<T>, start: usize) {
+    let children = self.nodes.split_off(start);
+    let node = Node::Tree(label.to_owned(), ast.span(id), self.typ(id), children);
+    self.nodes.push(node);
+  }
+
+  fn typ<T>(&self, id: NodeId<T>) -> Option<Type> {
+    self.types.and_then(|types| types.of(id))
+  }
+}
+
+impl<'a> Visitor for Builder<'a> {
+  fn visit_block(&mut self, ast: &Ast, block: NodeId<Block>) {
+    let start = self.nodes.len();
+    walk_block(self, ast, block);
+    self.tree(ast, "Block", block, start);
+  }
+
+  fn visit_assignment(&mut self, ast: &Ast, assig: NodeId<Assignment>) {
+    let start = self.nodes.len();
+    // Declared names are tokens, not nodes, so they have no type
+    let ident = TokenSpan::new(ast.ident_token(assig), 1);
+    let (label, name) = match &ast[assig] {
+      Assignment::Initialization(Mutability::Immutable, name, ..) => {
+        ("Initialization", name.clone())
+      }
+      Assignment::Initialization(Mutability::Mutable, name, ..) => {
+        ("Initialization", format!("mut {}", name))
+      }
+      Assignment::Reassignment(name, _) => ("Reassignment", name.clone()),
+    };
+    self.nodes.push(Node::Leaf(name, ident, None));
+
+    walk_assignment(self, ast, assig);
+    self.tree(ast, label, assig, start);
+  }
+
+  fn visit_term(&mut self, ast: &Ast, term: NodeId<Term>) {
+    let start = self.nodes.len();
+    walk_term(self, ast, term);
+    if let Term::Operation(_, op, _) = ast[term] {
+      let label = match op {
+        TermOp::Add => "Add",
+        TermOp::Subtract => "Subtract",
+      };
+      self.tree(ast, label, term, start);
+    }
+  }
+
+  fn visit_factor(&mut self, ast: &Ast, factor: NodeId<Factor>) {
+    let start = self.nodes.len();
+    walk_factor(self, ast, factor);
+    if let Factor::Operation(_, op, _) = ast[factor] {
+      let label = match op {
+        FactorOp::Multiply => "Multiply",
+        FactorOp::Divide => "Divide",
+        FactorOp::Modulo => "Modulo",
+      };
+      self.tree(ast, label, factor, start);
+    }
+  }
+
+  fn visit_leaf(&mut self, ast: &Ast, leaf: NodeId<Leaf>) {
+    match &ast[leaf] {
+      Leaf::Identifier(val) | Leaf::FloatLiteral(val) => {
+        let node = Node::Leaf(val.clone(), ast.span(leaf), self.typ(leaf));
+        self.nodes.push(node);
+      }
+      Leaf::Term(_) => walk_leaf(self, ast, leaf),
+    }
+  }
+}
@@ -0,0 +1,238 @@
+use crate::ir::*;
+use crate::types::Type;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Exact floating-point remainder, as `%` in Rust and `fmod` in C,
+/// which WebAssembly lacks. Multiples of `|y|` scaled by powers of
+/// two are subtracted from `|x|` while they fit: each subtraction is
+/// exact since `t <= r < 2 * t`
+const FMOD: &str = "  (func $fmod (param $x f64) (param $y f64) (result f64)
+    (local $r f64)
+    (local $m f64)
+    (local $t f64)
+    local.get $x
+    f64.abs
+    local.set $r
+    local.get $y
+    f64.abs
+    local.set $m
+    ;; NaN for an infinite x, a zero y or NaN operands
+    local.get $r
+    f64.const inf
+    f64.eq
+    local.get $m
+    f64.const 0
+    f64.eq
+    i32.or
+    local.get $r
+    local.get $r
+    f64.ne
+    i32.or
+    local.get $m
+    local.get $m
+    f64.ne
+    i32.or
+    if
+      f64.const nan
+      return
+    end
+    local.get $r
+    local.get $m
+    f64.lt
+    if
+      local.get $x
+      return
+    end
+    local.get $m
+    local.set $t
+    block $scaled
+      loop $scale
+        local.get $t
+        f64.const 2
+        f64.mul
+        local.get $r
+        f64.gt
+        br_if $scaled
+        local.get $t
+        f64.const 2
+        f64.mul
+        local.set $t
+        br $scale
+      end
+    end
+    block $done
+      loop $subtract
+        local.get $t
+        local.get $m
+        f64.lt
+        br_if $done
+        local.get $r
+        local.get $t
+        f64.ge
+        if
+          local.get $r
+          local.get $t
+          f64.sub
+          local.set $r
+        end
+        local.get $t
+        f64.const 0.5
+        f64.mul
+        local.set $t
+        br $subtract
+      end
+    end
+    local.get $r
+    local.get $x
+    f64.copysign
+  )
+";
+
+/// Emits a WebAssembly text module exporting `main`, which returns
+/// the program's value. Variables and registers become locals;
+/// `void` values have no representation
+pub fn emit(function: &Function) -> String {
+  let mut out = String::from("(module\n");
+  let uses_modulo = function.blocks.iter().any(|block| {
+    block
+      .instructions
+      .iter()
+      .any(|instruction| matches!(instruction, Instruction::Binary(_, BinaryOp::Modulo, _, _)))
+  });
+  if uses_modulo {
+    out += FMOD;
+  }
+
+  write!(out, "  (func $main (export \"main\")").unwrap();
+  if function.ret != Type::Void {
+    out += " (result f64)";
+  }
+  out += "\n";
+
+  let vars = var_names(function);
+  for (var, name) in function.variables.iter().zip(&vars) {
+    if var.typ != Type::Void {
+      writeln!(out, "    (local {} f64)", name).unwrap();
+    }
+  }
+  for reg in 0..function.registers.len() {
+    writeln!(out, "    (local $%{} f64)", reg).unwrap();
+  }
+
+  // Jumps between blocks go through a dispatch loop, which
+  // branches into the block whose index is in `$%bb`
+  let dispatch = function.blocks.len() > 1;
+  let mut indent = "    ".to_owned();
+  if dispatch {
+    out += "    (local $%bb i32)\n";
+    out += "    loop $dispatch\n";
+    for i in (0..function.blocks.len()).rev() {
+      writeln!(out, "      block $bb{}", i).unwrap();
+    }
+    out += "        local.get $%bb\n        br_table";
+    for i in 0..function.blocks.len() {
+      write!(out, " $bb{}", i).unwrap();
+    }
+    out += "\n";
+    indent += "  ";
+  }
+
+  for (i, block) in function.blocks.iter().enumerate() {
+    if dispatch {
+      writeln!(out, "      end ;; bb{}", i).unwrap();
+    }
+    let mut line = |instr: String| writeln!(out, "{}{}", indent, instr).unwrap();
+
+    for instruction in &block.instructions {
+      match instruction {
+        Instruction::Const(dest, value) => {
+          line(format!("f64.const {}", float(*value)));
+          line(format!("local.set $%{}", dest.0));
+        }
+        Instruction::Binary(dest, op, lhs, rhs) => {
+          line(format!("local.get $%{}", lhs.0));
+          line(format!("local.get $%{}", rhs.0));
+          line(
+            match op {
+              BinaryOp::Add => "f64.add",
+              BinaryOp::Subtract => "f64.sub",
+              BinaryOp::Multiply => "f64.mul",
+              BinaryOp::Divide => "f64.div",
+              BinaryOp::Modulo => "call $fmod",
+            }
+            .to_owned(),
+          );
+          line(format!("local.set $%{}", dest.0));
+        }
+        Instruction::Load(dest, src) => {
+          line(format!("local.get {}", vars[src.0]));
+          line(format!("local.set $%{}", dest.0));
+        }
+        Instruction::Store(dest, src) => {
+          line(format!("local.get $%{}", src.0));
+          line(format!("local.set {}", vars[dest.0]));
+        }
+      }
+    }
+
+    match &block.terminator {
+      Terminator::Jump(target) => {
+        line(format!("i32.const {}", target.0));
+        line("local.set $%bb".to_owned());
+        line("br $dispatch".to_owned());
+      }
+      Terminator::Return(value) => {
+        if let Some(value) = value {
+          line(format!("local.get $%{}", value.0));
+        }
+        line("return".to_owned());
+      }
+    }
+  }
+
+  if dispatch {
+    out += "    end\n    unreachable\n";
+  }
+  out += "  )\n)\n";
+  out
+}
+
+/// Local names of variables. Caravel identifiers that aren't valid
+/// in WAT, or shared by several variables, are numbered instead
+fn var_names(function: &Function) -> Vec<String> {
+  let mut seen = HashSet::new();
+  let duplicated: HashSet<&str> = function
+    .variables
+    .iter()
+    .filter(|var| !seen.insert(&var.name[..]))
+    .map(|var| &var.name[..])
+    .collect();
+
+  function
+    .variables
+    .iter()
+    .enumerate()
+    .map(|(i, var)| {
+      let valid = var
+        .name
+        .chars()
+        .all(|char| char.is_ascii_alphanumeric() || char == '_');
+      match &var.name {
+        name if valid && !duplicated.contains(&name[..]) => format!("${}", name),
+        name if valid => format!("${}.{}", name, i),
+        _ => format!("$var.{}", i),
+      }
+    })
+    .collect()
+}
+
+fn float(value: f64) -> String {
+  if value.is_nan() {
+    "nan".to_owned()
+  } else {
+    // Debug formatting keeps the digits needed to round-trip, and
+    // writes infinities as `inf`
+    format!("{:?}", value)
+  }
+}
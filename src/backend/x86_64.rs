@@ -0,0 +1,393 @@
+//! GNU assembler (AT&T syntax) for Linux x86-64, to be linked against
+//! the C library. Variables live in stack slots below `%rbp`; each
+//! arithmetic expression is evaluated in SSE registers, which are
+//! allocated by linear scan over the expression tree
+
+use crate::interpreter::parse_float;
+use crate::ir::BinaryOp;
+use crate::parser::ast::*;
+use crate::resolver::{Resolution, SymbolId};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Registers handed out by the allocator. `%xmm14` and `%xmm15`
+/// are kept free as scratch registers
+const REGISTERS: u8 = 14;
+
+/// Prints `%xmm0` with enough digits to read it back exactly
+const PRINT: &str = "
+caravel_print:
+  subq $8, %rsp
+  leaq .Lformat(%rip), %rdi
+  movl $1, %eax
+  call printf@PLT
+  addq $8, %rsp
+  ret
+";
+
+/// Emits a program whose `main` prints the value of the program,
+/// if it isn't `void`
+pub fn emit(ast: &Ast, resolution: &Resolution) -> String {
+  let mut emitter = Emitter {
+    ast,
+    resolution,
+    constants: Vec::new(),
+    constant_indices: HashMap::new(),
+    code: String::new(),
+    spill_slots: 0,
+  };
+  let result = emitter.block(ast.root());
+  if let Some(result) = &result {
+    if result != "%xmm0" {
+      emitter.line(format!("movsd {}, %xmm0", result));
+    }
+    emitter.line("call caravel_print".to_owned());
+  }
+
+  let slots = resolution.declarations.len() + 2 + emitter.spill_slots;
+  let mut out = String::from("  .text\n  .globl main\nmain:\n  pushq %rbp\n  movq %rsp, %rbp\n");
+  // The stack stays aligned to 16 bytes for calls
+  writeln!(out, "  subq ${}, %rsp", (slots * 8).div_ceil(16) * 16).unwrap();
+  out += &emitter.code;
+  out += "  xorl %eax, %eax\n  leave\n  ret\n";
+  if result.is_some() {
+    out += PRINT;
+  }
+
+  out += "\n  .section .rodata\n  .p2align 3\n";
+  for (i, constant) in emitter.constants.iter().enumerate() {
+    writeln!(
+      out,
+      ".LC{}:\n  .quad {:#018x} # {}",
+      i,
+      constant.to_bits(),
+      constant
+    )
+    .unwrap();
+  }
+  if result.is_some() {
+    out += ".Lformat:\n  .string \"%.17g\\n\"\n";
+  }
+  out += "  .section .note.GNU-stack,\"\",@progbits\n";
+  out
+}
+
+/// Operand of an expression node: a memory operand, or the result
+/// of an earlier node
+enum Operand {
+  Memory(String),
+  Node(usize),
+}
+
+struct Node {
+  op: BinaryOp,
+  lhs: Operand,
+  rhs: Operand,
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Location {
+  Register(u8),
+  /// Index of a spill slot
+  Spilled(usize),
+}
+
+struct Emitter<'a> {
+  ast: &'a Ast,
+  resolution: &'a Resolution,
+  constants: Vec<f64>,
+  /// Bits of every constant -> its index in `constants`
+  constant_indices: HashMap<u64, usize>,
+  code: String,
+  /// Most spill slots used by an expression
+  spill_slots: usize,
+}
+
+impl<'a> Emitter<'a> {
+  // Each function returns the operand holding the value, which
+  // stays valid until the next expression is evaluated, or `None`
+  // for `void`
+
+  fn block(&mut self, block: NodeId<Block>) -> Option<String> {
+    let mut value = None;
+    for &expr in &self.ast[block].expressions {
+      value = self.expression(expr);
+    }
+    value
+  }
+
+  fn expression(&mut self, expr: NodeId<Expression>) -> Option<String> {
+    match self.ast[expr] {
+      Expression::Assignment(assig) => self.assignment(assig),
+      Expression::Block(block) => self.block(block),
+      Expression::Term(term) => {
+        let mut nodes = Vec::new();
+        match self.term(term, &mut nodes)? {
+          Operand::Memory(operand) => Some(operand),
+          Operand::Node(_) => Some(self.evaluate(&nodes)),
+        }
+      }
+    }
+  }
+
+  fn assignment(&mut self, assig: NodeId<Assignment>) -> Option<String> {
+    let val = match self.ast[assig] {
+      Assignment::Initialization(_, _, _, None) => return None,
+      Assignment::Initialization(_, _, _, Some(val)) | Assignment::Reassignment(_, val) => {
+        self.expression(val)?
+      }
+    };
+
+    // The analyzer has checked that every assigned variable is declared
+    let id = self
+      .resolution
+      .symbol_at(self.ast.ident_token(assig))
+      .unwrap();
+    let var = slot(id.0 + 1);
+    self.store(&val, &var);
+    Some(var)
+  }
+
+  // Expressions are flattened into nodes in evaluation order, with
+  // every node used once, by the node that follows its last operand
+
+  fn term(&mut self, term: NodeId<Term>, nodes: &mut Vec<Node>) -> Option<Operand> {
+    match self.ast[term] {
+      Term::Factor(factor) => self.factor(factor, nodes),
+      Term::Operation(lhs, op, rhs) => {
+        let (lhs, rhs) = (self.term(lhs, nodes)?, self.factor(rhs, nodes)?);
+        let op = match op {
+          TermOp::Add => BinaryOp::Add,
+          TermOp::Subtract => BinaryOp::Subtract,
+        };
+        nodes.push(Node { op, lhs, rhs });
+        Some(Operand::Node(nodes.len() - 1))
+      }
+    }
+  }
+
+  fn factor(&mut self, factor: NodeId<Factor>, nodes: &mut Vec<Node>) -> Option<Operand> {
+    match self.ast[factor] {
+      Factor::Leaf(leaf) => self.leaf(leaf, nodes),
+      Factor::Operation(lhs, op, rhs) => {
+        let (lhs, rhs) = (self.factor(lhs, nodes)?, self.leaf(rhs, nodes)?);
+        let op = match op {
+          FactorOp::Multiply => BinaryOp::Multiply,
+          FactorOp::Divide => BinaryOp::Divide,
+          FactorOp::Modulo => BinaryOp::Modulo,
+        };
+        nodes.push(Node { op, lhs, rhs });
+        Some(Operand::Node(nodes.len() - 1))
+      }
+    }
+  }
+
+  fn leaf(&mut self, leaf: NodeId<Leaf>, nodes: &mut Vec<Node>) -> Option<Operand> {
+    match &self.ast[leaf] {
+      Leaf::FloatLiteral(value) => Some(Operand::Memory(self.constant(parse_float(value)))),
+      Leaf::Identifier(_) => {
+        let id: SymbolId = self.resolution.uses[&self.ast.span(leaf).start];
+        if self.resolution.declaration(id).type_ident == "void" {
+          return None;
+        }
+        Some(Operand::Memory(slot(id.0 + 1)))
+      }
+      Leaf::Term(term) => self.term(*term, nodes),
+    }
+  }
+
+  /// Emits the nodes of an expression, returning the result of the
+  /// last. A node's value is live from the node until its user; when
+  /// more values are live than there are registers, the one used
+  /// furthest away goes to a spill slot
+  fn evaluate(&mut self, nodes: &[Node]) -> String {
+    let mut users = vec![nodes.len(); nodes.len()];
+    for (i, node) in nodes.iter().enumerate() {
+      for operand in [&node.lhs, &node.rhs] {
+        if let Operand::Node(n) = operand {
+          users[*n] = i;
+        }
+      }
+    }
+
+    let mut locations: Vec<Location> = Vec::new();
+    // Nodes whose values are live
+    let mut active: Vec<usize> = Vec::new();
+    let mut free_registers: Vec<u8> = (0..REGISTERS).rev().collect();
+    let mut free_slots: Vec<usize> = Vec::new();
+    let mut slots = 0;
+
+    for (i, node) in nodes.iter().enumerate() {
+      let operand = |operand: &Operand, locations: &Vec<Location>| match operand {
+        Operand::Memory(memory) => memory.clone(),
+        Operand::Node(n) => self.location(locations[*n]),
+      };
+      let (lhs, rhs) = (
+        operand(&node.lhs, &locations),
+        operand(&node.rhs, &locations),
+      );
+
+      // The operands are used for the last time here. Their slots
+      // can only be reused once they have been read
+      let mut released_slots = Vec::new();
+      active.retain(|n| match users[*n] == i {
+        true => {
+          match locations[*n] {
+            Location::Register(reg) => free_registers.push(reg),
+            Location::Spilled(slot) => released_slots.push(slot),
+          }
+          false
+        }
+        false => true,
+      });
+
+      let mut new_slot = || {
+        free_slots.pop().unwrap_or_else(|| {
+          slots += 1;
+          slots - 1
+        })
+      };
+      // Computing into the register of the left operand saves a move
+      let lhs_register = match node.lhs {
+        Operand::Node(n) => free_registers
+          .iter()
+          .position(|reg| locations[n] == Location::Register(*reg)),
+        Operand::Memory(_) => None,
+      };
+      let register = match lhs_register {
+        Some(index) => Some(free_registers.remove(index)),
+        None => free_registers.pop(),
+      };
+      let location = match register {
+        Some(reg) => Location::Register(reg),
+        None => {
+          let furthest = active
+            .iter()
+            .filter(|n| matches!(locations[**n], Location::Register(_)))
+            .max_by_key(|n| users[**n]);
+          match furthest {
+            Some(&furthest) if users[furthest] > users[i] => {
+              let reg = locations[furthest];
+              let spilled = Location::Spilled(new_slot());
+              self.line(format!(
+                "movsd {}, {}",
+                self.location(reg),
+                self.location(spilled)
+              ));
+              locations[furthest] = spilled;
+              reg
+            }
+            _ => Location::Spilled(new_slot()),
+          }
+        }
+      };
+      locations.push(location);
+      active.push(i);
+
+      let dest = match location {
+        Location::Register(reg) => format!("%xmm{}", reg),
+        Location::Spilled(_) => "%xmm15".to_owned(),
+      };
+      self.binary(node.op, &lhs, &rhs, &dest);
+      if let Location::Spilled(_) = location {
+        self.line(format!("movsd %xmm15, {}", self.location(location)));
+      }
+      free_slots.extend(released_slots);
+    }
+
+    self.spill_slots = self.spill_slots.max(slots);
+    self.location(locations[nodes.len() - 1])
+  }
+
+  /// `dest = lhs op rhs`, where `dest` is a register that
+  /// may also hold an operand
+  fn binary(&mut self, op: BinaryOp, lhs: &str, rhs: &str, dest: &str) {
+    let instr = match op {
+      BinaryOp::Add => "addsd",
+      BinaryOp::Subtract => "subsd",
+      BinaryOp::Multiply => "mulsd",
+      BinaryOp::Divide => "divsd",
+      BinaryOp::Modulo => return self.modulo(lhs, rhs, dest),
+    };
+
+    if rhs == dest && lhs != dest {
+      if let BinaryOp::Add | BinaryOp::Multiply = op {
+        self.line(format!("{} {}, {}", instr, lhs, dest));
+        return;
+      }
+      self.line(format!("movsd {}, %xmm14", rhs));
+      self.line(format!("movsd {}, {}", lhs, dest));
+      self.line(format!("{} %xmm14, {}", instr, dest));
+      return;
+    }
+
+    if lhs != dest {
+      self.line(format!("movsd {}, {}", lhs, dest));
+    }
+    self.line(format!("{} {}, {}", instr, rhs, dest));
+  }
+
+  /// SSE has no remainder, but the x87 partial remainder is exact,
+  /// like `fmod`, once it's repeated until it reports completion
+  fn modulo(&mut self, lhs: &str, rhs: &str, dest: &str) {
+    let (x, y) = (
+      slot(self.resolution.declarations.len() + 1),
+      slot(self.resolution.declarations.len() + 2),
+    );
+    self.store(lhs, &x);
+    self.store(rhs, &y);
+    self.line(format!("fldl {}", y));
+    self.line(format!("fldl {}", x));
+    self.code += "1:\n";
+    self.line("fprem".to_owned());
+    self.line("fnstsw %ax".to_owned());
+    self.line("testw $0x400, %ax".to_owned());
+    self.line("jnz 1b".to_owned());
+    self.line("fstp %st(1)".to_owned());
+    self.line(format!("fstpl {}", x));
+    self.line(format!("movsd {}, {}", x, dest));
+  }
+
+  /// Copies a value to memory
+  fn store(&mut self, src: &str, dest: &str) {
+    if src == dest {
+      return;
+    }
+    if src.starts_with('%') {
+      self.line(format!("movsd {}, {}", src, dest));
+    } else {
+      self.line(format!("movsd {}, %xmm15", src));
+      self.line(format!("movsd %xmm15, {}", dest));
+    }
+  }
+
+  fn location(&self, location: Location) -> String {
+    match location {
+      Location::Register(reg) => format!("%xmm{}", reg),
+      // Spill slots come after the variables and the two slots
+      // used by `modulo`
+      Location::Spilled(n) => slot(self.resolution.declarations.len() + 3 + n),
+    }
+  }
+
+  fn constant(&mut self, value: f64) -> String {
+    let constants = &mut self.constants;
+    let index = *self
+      .constant_indices
+      .entry(value.to_bits())
+      .or_insert_with(|| {
+        constants.push(value);
+        constants.len() - 1
+      });
+    format!(".LC{}(%rip)", index)
+  }
+
+  fn line(&mut self, instr: String) {
+    writeln!(self.code, "  {}", instr).unwrap();
+  }
+}
+
+/// The `n`th 8-byte stack slot, counting from one
+fn slot(n: usize) -> String {
+  format!("-{}(%rbp)", n * 8)
+}
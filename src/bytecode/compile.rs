@@ -0,0 +1,165 @@
+use super::*;
+use crate::interpreter::parse_float;
+use crate::parser::ast::*;
+use crate::resolver::Resolution;
+use std::collections::HashMap;
+
+/// Compiles an analyzed program into its `main` function. Every
+/// variable gets its own slot, and `void` values are never pushed
+pub fn compile(ast: &Ast, resolution: &Resolution) -> Program {
+  let mut compiler = Compiler {
+    ast,
+    resolution,
+    constants: Vec::new(),
+    constant_indices: HashMap::new(),
+    code: Vec::new(),
+  };
+  let returns_value = compiler.block(ast.root());
+  compiler.code.push(Op::Return);
+
+  Program {
+    constants: compiler.constants,
+    functions: vec![Function {
+      name: "main".to_owned(),
+      arity: 0,
+      locals: resolution
+        .declarations
+        .iter()
+        .map(|declaration| declaration.name.clone())
+        .collect(),
+      returns_value,
+      code: compiler.code,
+    }],
+  }
+}
+
+struct Compiler<'a> {
+  ast: &'a Ast,
+  resolution: &'a Resolution,
+  constants: Vec<f64>,
+  /// Bits of every constant -> its index in `constants`
+  constant_indices: HashMap<u64, u32>,
+  code: Vec<Op>,
+}
+
+impl<'a> Compiler<'a> {
+  // Each function returns whether the compiled code pushes a value
+
+  fn block(&mut self, block: NodeId<Block>) -> bool {
+    let mut pushed = false;
+    for &expr in &self.ast[block].expressions {
+      if pushed {
+        self.discard();
+      }
+      pushed = self.expression(expr);
+    }
+    pushed
+  }
+
+  /// Drops the value pushed by the last expression
+  fn discard(&mut self) {
+    // An assignment doesn't need to keep a copy of its value
+    match self.code[..] {
+      [.., Op::Dup, Op::Store(slot)] => {
+        self.code.pop();
+        *self.code.last_mut().unwrap() = Op::Store(slot);
+      }
+      _ => self.code.push(Op::Pop),
+    }
+  }
+
+  fn expression(&mut self, expr: NodeId<Expression>) -> bool {
+    match self.ast[expr] {
+      Expression::Assignment(assig) => self.assignment(assig),
+      Expression::Block(block) => self.block(block),
+      Expression::Term(term) => self.term(term),
+    }
+  }
+
+  fn assignment(&mut self, assig: NodeId<Assignment>) -> bool {
+    let val = match self.ast[assig] {
+      Assignment::Initialization(_, _, _, None) => return false,
+      Assignment::Initialization(_, _, _, Some(val)) | Assignment::Reassignment(_, val) => val,
+    };
+    if !self.expression(val) {
+      return false;
+    }
+
+    // The analyzer has checked that every assigned variable is declared
+    let slot = self
+      .resolution
+      .symbol_at(self.ast.ident_token(assig))
+      .unwrap()
+      .0;
+    // The assignment's own value stays on the stack
+    self.code.push(Op::Dup);
+    self.code.push(Op::Store(slot as u32));
+    true
+  }
+
+  fn term(&mut self, term: NodeId<Term>) -> bool {
+    match self.ast[term] {
+      Term::Factor(factor) => self.factor(factor),
+      Term::Operation(lhs, op, rhs) => {
+        // The analyzer only admits operands of the same type
+        if !(self.term(lhs) & self.factor(rhs)) {
+          return false;
+        }
+        self.code.push(match op {
+          TermOp::Add => Op::Add,
+          TermOp::Subtract => Op::Subtract,
+        });
+        true
+      }
+    }
+  }
+
+  fn factor(&mut self, factor: NodeId<Factor>) -> bool {
+    match self.ast[factor] {
+      Factor::Leaf(leaf) => self.leaf(leaf),
+      Factor::Operation(lhs, op, rhs) => {
+        if !(self.factor(lhs) & self.leaf(rhs)) {
+          return false;
+        }
+        self.code.push(match op {
+          FactorOp::Multiply => Op::Multiply,
+          FactorOp::Divide => Op::Divide,
+          FactorOp::Modulo => Op::Modulo,
+        });
+        true
+      }
+    }
+  }
+
+  /// Index of `value` in the constants pool, adding it if it's new
+  fn constant(&mut self, value: f64) -> u32 {
+    let constants = &mut self.constants;
+    *self
+      .constant_indices
+      .entry(value.to_bits())
+      .or_insert_with(|| {
+        constants.push(value);
+        (constants.len() - 1) as u32
+      })
+  }
+
+  fn leaf(&mut self, leaf: NodeId<Leaf>) -> bool {
+    match &self.ast[leaf] {
+      Leaf::FloatLiteral(value) => {
+        let index = self.constant(parse_float(value));
+        self.code.push(Op::Const(index));
+        true
+      }
+      Leaf::Identifier(_) => {
+        let start = self.ast.span(leaf).start;
+        let id = self.resolution.uses[&start];
+        if self.resolution.declaration(id).type_ident == "void" {
+          return false;
+        }
+        self.code.push(Op::Load(id.0 as u32));
+        true
+      }
+      Leaf::Term(term) => self.term(*term),
+    }
+  }
+}
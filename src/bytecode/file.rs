@@ -0,0 +1,164 @@
+//! The `.cvc` file format: a magic number and format version, then the
+//! constants pool and functions. Integers are little-endian; `u32`s
+//! also prefix the lengths of lists and strings
+
+use super::*;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+
+pub const MAGIC: &[u8; 4] = b"CVC\0";
+/// Incremented whenever the encoding of a `Program` changes
+pub const VERSION: u16 = 1;
+
+pub fn write(program: &Program, output: &mut impl Write) -> io::Result<()> {
+  output.write_all(MAGIC)?;
+  output.write_all(&VERSION.to_le_bytes())?;
+
+  write_u32(output, program.constants.len())?;
+  for constant in &program.constants {
+    output.write_all(&constant.to_le_bytes())?;
+  }
+
+  write_u32(output, program.functions.len())?;
+  for function in &program.functions {
+    write_str(output, &function.name)?;
+    output.write_all(&function.arity.to_le_bytes())?;
+    write_u32(output, function.locals.len())?;
+    for local in &function.locals {
+      write_str(output, local)?;
+    }
+    output.write_all(&[function.returns_value as u8])?;
+
+    write_u32(output, function.code.len())?;
+    for op in &function.code {
+      let (opcode, operand) = match *op {
+        Op::Const(index) => (0, Some(index)),
+        Op::Load(slot) => (1, Some(slot)),
+        Op::Store(slot) => (2, Some(slot)),
+        Op::Add => (3, None),
+        Op::Subtract => (4, None),
+        Op::Multiply => (5, None),
+        Op::Divide => (6, None),
+        Op::Modulo => (7, None),
+        Op::Pop => (8, None),
+        Op::Dup => (9, None),
+        Op::Jump(target) => (10, Some(target)),
+        Op::JumpIfZero(target) => (11, Some(target)),
+        Op::Call(index) => (12, Some(index)),
+        Op::Return => (13, None),
+      };
+      output.write_all(&[opcode])?;
+      if let Some(operand) = operand {
+        output.write_all(&operand.to_le_bytes())?;
+      }
+    }
+  }
+
+  output.flush()
+}
+
+/// Reads a program written by `write`. Files from other versions of
+/// the format are rejected rather than guessed at
+pub fn read(input: &mut impl Read) -> io::Result<Program> {
+  let mut magic = [0; 4];
+  input.read_exact(&mut magic)?;
+  if &magic != MAGIC {
+    return Err(invalid_data("not a Caravel bytecode file"));
+  }
+  let version = u16::from_le_bytes(read_bytes(input)?);
+  if version != VERSION {
+    return Err(invalid_data(&format!(
+      "unsupported bytecode version {} (expected {})",
+      version, VERSION
+    )));
+  }
+
+  let mut constants = Vec::new();
+  for _ in 0..read_u32(input)? {
+    constants.push(f64::from_le_bytes(read_bytes(input)?));
+  }
+
+  let mut functions = Vec::new();
+  for _ in 0..read_u32(input)? {
+    let name = read_str(input)?;
+    let arity = read_u32(input)?;
+    let mut locals = Vec::new();
+    for _ in 0..read_u32(input)? {
+      locals.push(read_str(input)?);
+    }
+    let returns_value = match read_bytes::<1>(input)? {
+      [0] => false,
+      [1] => true,
+      _ => return Err(invalid_data("invalid boolean")),
+    };
+
+    let mut code = Vec::new();
+    for _ in 0..read_u32(input)? {
+      let [opcode] = read_bytes(input)?;
+      code.push(match opcode {
+        0 => Op::Const(read_u32(input)?),
+        1 => Op::Load(read_u32(input)?),
+        2 => Op::Store(read_u32(input)?),
+        3 => Op::Add,
+        4 => Op::Subtract,
+        5 => Op::Multiply,
+        6 => Op::Divide,
+        7 => Op::Modulo,
+        8 => Op::Pop,
+        9 => Op::Dup,
+        10 => Op::Jump(read_u32(input)?),
+        11 => Op::JumpIfZero(read_u32(input)?),
+        12 => Op::Call(read_u32(input)?),
+        13 => Op::Return,
+        _ => return Err(invalid_data(&format!("unknown opcode {}", opcode))),
+      });
+    }
+
+    functions.push(Function {
+      name,
+      arity,
+      locals,
+      returns_value,
+      code,
+    });
+  }
+
+  Ok(Program {
+    constants,
+    functions,
+  })
+}
+
+fn write_u32(output: &mut impl Write, value: usize) -> io::Result<()> {
+  let value = u32::try_from(value).map_err(|_| invalid_data("list too long"))?;
+  output.write_all(&value.to_le_bytes())
+}
+
+fn write_str(output: &mut impl Write, str: &str) -> io::Result<()> {
+  write_u32(output, str.len())?;
+  output.write_all(str.as_bytes())
+}
+
+fn read_bytes<const N: usize>(input: &mut impl Read) -> io::Result<[u8; N]> {
+  let mut bytes = [0; N];
+  input.read_exact(&mut bytes)?;
+  Ok(bytes)
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+  Ok(u32::from_le_bytes(read_bytes(input)?))
+}
+
+fn read_str(input: &mut impl Read) -> io::Result<String> {
+  let len = read_u32(input)? as u64;
+  let mut bytes = Vec::new();
+  input.take(len).read_to_end(&mut bytes)?;
+  if bytes.len() as u64 != len {
+    return Err(io::ErrorKind::UnexpectedEof.into());
+  }
+  String::from_utf8(bytes).map_err(|_| invalid_data("invalid UTF-8 in name"))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg)
+}
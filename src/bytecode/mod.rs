@@ -0,0 +1,127 @@
+//! Instructions for the stack-based virtual machine in `vm`.
+//! Operands are popped from and results pushed to the value stack;
+//! variables live in local slots of the current call frame
+
+pub mod compile;
+pub mod file;
+pub mod vm;
+
+use std::fmt::{self, Display};
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Op {
+  /// Pushes a value from the constants pool
+  Const(u32),
+  /// Pushes the value of a local slot
+  Load(u32),
+  /// Pops a value into a local slot
+  Store(u32),
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  Modulo,
+  /// Discards the top of the stack
+  Pop,
+  /// Pushes a copy of the top of the stack
+  Dup,
+  /// Continues at an instruction index of the current function
+  Jump(u32),
+  /// Pops a value, and jumps if it's zero
+  JumpIfZero(u32),
+  /// Calls a function, which pops its arguments into its first slots
+  Call(u32),
+  /// Returns to the caller, with the top of the
+  /// stack if the function returns a value
+  Return,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Function {
+  pub name: String,
+  pub arity: u32,
+  /// Name of the variable in each local slot, arguments first
+  pub locals: Vec<String>,
+  pub returns_value: bool,
+  pub code: Vec<Op>,
+}
+
+/// A compiled program. Execution starts with the first function
+#[derive(Clone, PartialEq, Debug)]
+pub struct Program {
+  pub constants: Vec<f64>,
+  pub functions: Vec<Function>,
+}
+
+/// Disassembly, one instruction per line
+impl Display for Program {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "constants:")?;
+    for (i, constant) in self.constants.iter().enumerate() {
+      writeln!(f, "  {:>4}  {}", i, constant)?;
+    }
+
+    for function in &self.functions {
+      writeln!(
+        f,
+        "\nfn {} (arity {}, {} locals{}):",
+        function.name,
+        function.arity,
+        function.locals.len(),
+        if function.returns_value {
+          ", returns"
+        } else {
+          ""
+        }
+      )?;
+
+      for (i, op) in function.code.iter().enumerate() {
+        let local = |slot: &u32| match function.locals.get(*slot as usize) {
+          Some(name) => name.to_owned(),
+          None => "?".to_owned(),
+        };
+        let (name, operand, comment) = match op {
+          Op::Const(index) => (
+            "const",
+            Some(index),
+            self
+              .constants
+              .get(*index as usize)
+              .map(|constant| constant.to_string()),
+          ),
+          Op::Load(slot) => ("load", Some(slot), Some(local(slot))),
+          Op::Store(slot) => ("store", Some(slot), Some(local(slot))),
+          Op::Add => ("add", None, None),
+          Op::Subtract => ("sub", None, None),
+          Op::Multiply => ("mul", None, None),
+          Op::Divide => ("div", None, None),
+          Op::Modulo => ("rem", None, None),
+          Op::Pop => ("pop", None, None),
+          Op::Dup => ("dup", None, None),
+          Op::Jump(target) => ("jmp", Some(target), None),
+          Op::JumpIfZero(target) => ("jz", Some(target), None),
+          Op::Call(index) => (
+            "call",
+            Some(index),
+            self
+              .functions
+              .get(*index as usize)
+              .map(|function| function.name.clone()),
+          ),
+          Op::Return => ("ret", None, None),
+        };
+
+        let mut line = format!("  {:04}  {:<6}", i, name);
+        if let Some(operand) = operand {
+          line += &format!(" {:<4}", operand);
+        }
+        if let Some(comment) = comment {
+          line += &format!(" ; {}", comment);
+        }
+        writeln!(f, "{}", line.trim_end())?;
+      }
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,140 @@
+use super::*;
+
+struct Frame {
+  function: usize,
+  /// Index of the next instruction
+  ip: usize,
+  /// Index of the frame's first slot in `Vm::locals`
+  base: usize,
+}
+
+/// Runs `program`, returning the value of its first function, if any.
+/// Fails on bytecode that doesn't hold together, e.g. that refers to
+/// a missing constant or pops from an empty stack
+pub fn run(program: &Program) -> Result<Option<f64>, String> {
+  let mut vm = Vm {
+    program,
+    stack: Vec::new(),
+    locals: Vec::new(),
+    frames: Vec::new(),
+  };
+  vm.call(0)?;
+  vm.run()
+}
+
+struct Vm<'a> {
+  program: &'a Program,
+  stack: Vec<f64>,
+  locals: Vec<f64>,
+  frames: Vec<Frame>,
+}
+
+impl<'a> Vm<'a> {
+  fn run(&mut self) -> Result<Option<f64>, String> {
+    loop {
+      let frame = self.frames.last_mut().unwrap();
+      let function = &self.program.functions[frame.function];
+      let op = match function.code.get(frame.ip) {
+        Some(op) => *op,
+        None => return Err(format!("fn {} ends without returning", function.name)),
+      };
+      frame.ip += 1;
+      let base = frame.base;
+
+      match op {
+        Op::Const(index) => match self.program.constants.get(index as usize) {
+          Some(constant) => self.stack.push(*constant),
+          None => return Err(format!("missing constant {}", index)),
+        },
+        Op::Load(slot) => {
+          let value = *self.slot(base, slot)?;
+          self.stack.push(value);
+        }
+        Op::Store(slot) => {
+          let value = self.pop()?;
+          *self.slot(base, slot)? = value;
+        }
+        Op::Add => self.arithmetic(|lhs, rhs| lhs + rhs)?,
+        Op::Subtract => self.arithmetic(|lhs, rhs| lhs - rhs)?,
+        Op::Multiply => self.arithmetic(|lhs, rhs| lhs * rhs)?,
+        Op::Divide => self.arithmetic(|lhs, rhs| lhs / rhs)?,
+        Op::Modulo => self.arithmetic(|lhs, rhs| lhs % rhs)?,
+        Op::Pop => {
+          self.pop()?;
+        }
+        Op::Dup => {
+          let value = self.pop()?;
+          self.stack.extend([value, value]);
+        }
+        Op::Jump(target) => self.jump(target),
+        Op::JumpIfZero(target) => {
+          if self.pop()? == 0.0 {
+            self.jump(target);
+          }
+        }
+        Op::Call(index) => self.call(index as usize)?,
+        Op::Return => {
+          let frame = self.frames.pop().unwrap();
+          let value = match self.program.functions[frame.function].returns_value {
+            true => Some(self.pop()?),
+            false => None,
+          };
+          self.locals.truncate(frame.base);
+
+          if self.frames.is_empty() {
+            return Ok(value);
+          }
+          self.stack.extend(value);
+        }
+      }
+    }
+  }
+
+  fn call(&mut self, index: usize) -> Result<(), String> {
+    let function = match self.program.functions.get(index) {
+      Some(function) => function,
+      None => return Err(format!("missing function {}", index)),
+    };
+    let arity = function.arity as usize;
+    if arity > function.locals.len() || arity > self.stack.len() {
+      return Err(format!("bad call to fn {}", function.name));
+    }
+
+    let base = self.locals.len();
+    self
+      .locals
+      .extend(self.stack.drain(self.stack.len() - arity..));
+    self.locals.resize(base + function.locals.len(), 0.0);
+    self.frames.push(Frame {
+      function: index,
+      ip: 0,
+      base,
+    });
+    Ok(())
+  }
+
+  fn jump(&mut self, target: u32) {
+    // Jumps out of the code are caught when fetching the next instruction
+    self.frames.last_mut().unwrap().ip = target as usize;
+  }
+
+  fn slot(&mut self, base: usize, slot: u32) -> Result<&mut f64, String> {
+    let frame = self.frames.last().unwrap();
+    let len = self.program.functions[frame.function].locals.len();
+    match slot as usize {
+      slot if slot < len => Ok(&mut self.locals[base + slot]),
+      _ => Err(format!("missing local slot {}", slot)),
+    }
+  }
+
+  fn arithmetic(&mut self, op: impl Fn(f64, f64) -> f64) -> Result<(), String> {
+    let rhs = self.pop()?;
+    let lhs = self.pop()?;
+    self.stack.push(op(lhs, rhs));
+    Ok(())
+  }
+
+  fn pop(&mut self) -> Result<f64, String> {
+    self.stack.pop().ok_or_else(|| "stack underflow".to_owned())
+  }
+}
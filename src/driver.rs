@@ -0,0 +1,209 @@
+use crate::analyzer::{Analyzer, Types};
+use crate::backend;
+use crate::bytecode;
+use crate::error::{Error, ErrorKind, Result, Severity};
+use crate::fold::fold;
+use crate::ir;
+use crate::lexer::{confusables, lexer::Lexer, token::Token};
+use crate::parser::{ast::Ast, parser::Parser};
+use crate::position::source_position::SourceSpan;
+use crate::resolver::{resolve, Resolution};
+use crate::types::Type;
+use crate::unused;
+
+/// What `compile` renders into `CompileOutput::output`
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Emit {
+  /// The tokens, see `backend::tokens`. Available
+  /// even if the program doesn't parse
+  Tokens,
+  /// The tokens as JSON
+  TokensJson,
+  /// The syntax tree, as drawn by `backend::print`
+  Ast,
+  /// The syntax tree as a Graphviz graph, see `backend::dot`
+  AstDot,
+  /// The syntax tree as JSON, see `backend::json`
+  AstJson,
+  /// The intermediate representation, see `ir`
+  Ir,
+  /// A WebAssembly text module, see `backend::wasm`
+  Wat,
+  /// A C program printing the value, see `backend::c`
+  C,
+  /// x86-64 assembly, see `backend::x86_64`
+  Asm,
+  /// Disassembled bytecode, see `bytecode`
+  Bytecode,
+  /// Only check the program
+  Nothing,
+}
+
+impl Emit {
+  /// Parses the value of `--emit`
+  pub fn parse(str: &str) -> Option<Self> {
+    match str {
+      "tokens" => Some(Self::Tokens),
+      "tokens-json" => Some(Self::TokensJson),
+      "ast" => Some(Self::Ast),
+      "ast-dot" => Some(Self::AstDot),
+      "ast-json" => Some(Self::AstJson),
+      "ir" => Some(Self::Ir),
+      "wat" => Some(Self::Wat),
+      "c" => Some(Self::C),
+      "asm" => Some(Self::Asm),
+      "bytecode" => Some(Self::Bytecode),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct CompileOptions {
+  pub emit: Emit,
+  /// Whether `output` may contain ANSI escape codes
+  pub color: bool,
+}
+
+impl Default for CompileOptions {
+  fn default() -> Self {
+    Self {
+      emit: Emit::Ast,
+      color: false,
+    }
+  }
+}
+
+/// Everything produced by `compile`. Stages after a failing one
+/// do not run, so their fields are left as `None`
+#[derive(Debug, Default)]
+pub struct CompileOutput {
+  /// Warnings, followed by the error that stopped compilation, if any
+  pub diagnostics: Vec<Error>,
+  pub tokens: Option<Vec<Token>>,
+  /// Syntax tree, with constant arithmetic folded if analysis succeeds
+  pub ast: Option<Ast>,
+  pub resolution: Option<Resolution>,
+  /// Type of the program's value
+  pub ty: Option<Type>,
+  /// Type of every node of `ast`
+  pub types: Option<Types>,
+  /// The artifact requested by `CompileOptions::emit`
+  pub output: Option<String>,
+}
+
+impl CompileOutput {
+  /// Whether compilation finished without errors (warnings are allowed)
+  pub fn succeeded(&self) -> bool {
+    self
+      .diagnostics
+      .iter()
+      .all(|diagnostic| diagnostic.severity() == Severity::Warning)
+  }
+}
+
+/// Runs the whole pipeline over `source`
+pub fn compile(source: &str, options: &CompileOptions) -> CompileOutput {
+  let mut output = CompileOutput::default();
+
+  match Lexer::new(source).lex() {
+    Ok(tokens) => {
+      output.output = match options.emit {
+        Emit::Tokens => Some(backend::tokens::emit(&tokens, source)),
+        Emit::TokensJson => Some(backend::tokens::emit_json(&tokens, source)),
+        _ => None,
+      };
+      output.diagnostics.extend(confusables::check(&tokens));
+      if let Err(error) = compile_tokens(&tokens, options, &mut output) {
+        output.diagnostics.push(error);
+      }
+      output.tokens = Some(tokens);
+    }
+    Err(error) => output.diagnostics.push(error),
+  }
+
+  output
+}
+
+fn compile_tokens(
+  tokens: &Vec<Token>,
+  options: &CompileOptions,
+  output: &mut CompileOutput,
+) -> Result<()> {
+  let ast = output.ast.insert(Parser::new(tokens).parse()?);
+  let resolution = output.resolution.insert(resolve(ast));
+
+  let mut analyzer = Analyzer::new(tokens, ast);
+  output.ty = Some(analyzer.analyze()?);
+  let types = output.types.insert(analyzer.into_types());
+  output
+    .diagnostics
+    .extend(unused::check(ast, tokens, resolution));
+  output
+    .diagnostics
+    .extend(fold(ast, tokens, resolution, types));
+
+  output.output = match options.emit {
+    Emit::Ast => Some(backend::print::print(ast, Some(types), options.color)),
+    Emit::AstDot => Some(backend::dot::emit(ast, tokens)),
+    Emit::AstJson => Some(backend::json::emit(ast, types)),
+    Emit::Ir => Some(lower(ast, tokens, resolution)?.to_string()),
+    Emit::Wat => Some(backend::wasm::emit(&lower(ast, tokens, resolution)?)),
+    Emit::C => Some(backend::c::emit(ast, resolution)),
+    Emit::Asm => Some(backend::x86_64::emit(ast, resolution)),
+    Emit::Bytecode => Some(bytecode::compile::compile(ast, resolution).to_string()),
+    Emit::Tokens | Emit::TokensJson | Emit::Nothing => return Ok(()),
+  };
+
+  Ok(())
+}
+
+/// Lowers a program to IR. IR failing verification is a bug in
+/// the compiler, which is reported instead of emitting it
+fn lower(ast: &Ast, tokens: &[Token], resolution: &Resolution) -> Result<ir::Function> {
+  let function = ir::lower::lower(ast, resolution);
+  verified(function, ast.span(ast.root()).as_source_span(tokens))
+}
+
+fn verified(function: ir::Function, pos: SourceSpan) -> Result<ir::Function> {
+  match ir::verify::verify(&function) {
+    Ok(()) => Ok(function),
+    Err(msg) => Err(
+      Error::new(
+        ErrorKind::InternalError,
+        &format!("lowering produced invalid IR: {}", msg),
+        pos,
+      )
+      .with_help("this is a bug in caravel, please report it along with the program"),
+    ),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::verified;
+  use crate::error::ErrorKind;
+  use crate::ir::{BasicBlock, Function, Reg, Terminator};
+  use crate::position::source_position::DEFAULT_REAL_SPAN;
+  use crate::types::Type;
+
+  #[test]
+  fn reports_invalid_ir_as_an_internal_error() {
+    // Returns a register that is never assigned
+    let function = Function {
+      ret: Type::Float,
+      registers: vec![Type::Float],
+      variables: Vec::new(),
+      blocks: vec![BasicBlock {
+        instructions: Vec::new(),
+        terminator: Terminator::Return(Some(Reg(0))),
+      }],
+    };
+    let error = verified(function, DEFAULT_REAL_SPAN).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InternalError);
+    assert_eq!(
+      error.msg(),
+      "lowering produced invalid IR: bb0, terminator: %0 is used before it is assigned"
+    );
+  }
+}
@@ -0,0 +1,45 @@
+/// Long-form descriptions of every error code, one file per code
+/// under `explanations/`, compiled into the binary
+const EXPLANATIONS: &[(&str, &str)] = &[
+  ("E0001", include_str!("explanations/E0001.md")),
+  ("E0002", include_str!("explanations/E0002.md")),
+  ("E0003", include_str!("explanations/E0003.md")),
+  ("E0004", include_str!("explanations/E0004.md")),
+  ("E0005", include_str!("explanations/E0005.md")),
+  ("E0006", include_str!("explanations/E0006.md")),
+  ("E0007", include_str!("explanations/E0007.md")),
+  ("E0008", include_str!("explanations/E0008.md")),
+  ("E0009", include_str!("explanations/E0009.md")),
+  ("E0010", include_str!("explanations/E0010.md")),
+  ("E0011", include_str!("explanations/E0011.md")),
+  ("E0012", include_str!("explanations/E0012.md")),
+  ("E0013", include_str!("explanations/E0013.md")),
+  ("E0014", include_str!("explanations/E0014.md")),
+];
+
+/// Looks up the explanation for a code such as `E0003`
+pub fn explain(code: &str) -> Option<&'static str> {
+  EXPLANATIONS
+    .iter()
+    .find(|(c, _)| c.eq_ignore_ascii_case(code.trim()))
+    .map(|(_, text)| *text)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::explain;
+
+  #[test]
+  fn explains_known_codes() {
+    let text = explain("E0001").unwrap();
+    assert!(text.starts_with("The lexer encountered a character"));
+    assert_eq!(explain(" e0001\n"), Some(text));
+  }
+
+  #[test]
+  fn rejects_unknown_codes() {
+    assert_eq!(explain("E9999"), None);
+    assert_eq!(explain("0001"), None);
+    assert_eq!(explain(""), None);
+  }
+}
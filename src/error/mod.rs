@@ -0,0 +1,243 @@
+pub mod explain;
+#[cfg(test)]
+mod tests;
+
+use crate::ansi::{style, BOLD, RED, YELLOW};
+use crate::position::source_position::SourceSpan;
+use std::fmt::{self, Display, Write};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ErrorKind {
+  UnexpectedChar,
+  UnexpectedToken,
+  UndeclaredVariable,
+  TypeMismatch,
+  Redeclaration,
+  ConfusableIdentifier,
+  UnknownType,
+  UninitializedVariable,
+  InvalidRename,
+  UnusedVariable,
+  UnusedAssignment,
+  ImmutableAssignment,
+  DivisionByZero,
+  InternalError,
+}
+
+impl ErrorKind {
+  /// Stable identifier of the diagnostic, as accepted by `--explain`.
+  /// Codes are never reused or renumbered once released
+  pub fn code(&self) -> &'static str {
+    match self {
+      Self::UnexpectedChar => "E0001",
+      Self::UnexpectedToken => "E0002",
+      Self::UndeclaredVariable => "E0003",
+      Self::TypeMismatch => "E0004",
+      Self::Redeclaration => "E0005",
+      Self::ConfusableIdentifier => "E0006",
+      Self::UnknownType => "E0007",
+      Self::UninitializedVariable => "E0008",
+      Self::InvalidRename => "E0009",
+      Self::UnusedVariable => "E0010",
+      Self::UnusedAssignment => "E0011",
+      Self::ImmutableAssignment => "E0012",
+      Self::DivisionByZero => "E0013",
+      Self::InternalError => "E0014",
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::UnexpectedChar => "unexpected character",
+      Self::UnexpectedToken => "unexpected token",
+      Self::UndeclaredVariable => "use of undeclared variable",
+      Self::TypeMismatch => "type mismatch",
+      Self::Redeclaration => "multiple declarations for single variable",
+      Self::ConfusableIdentifier => "confusable identifier",
+      Self::UnknownType => "unknown type",
+      Self::UninitializedVariable => "use of uninitialized variable",
+      Self::InvalidRename => "invalid rename",
+      Self::UnusedVariable => "unused variable",
+      Self::UnusedAssignment => "value never read",
+      Self::ImmutableAssignment => "assignment to immutable variable",
+      Self::DivisionByZero => "division by zero",
+      Self::InternalError => "internal compiler error",
+    }
+  }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Severity {
+  /// Stops compilation
+  Error,
+  /// Reported, but compilation continues
+  Warning,
+}
+
+#[derive(Clone, Debug)]
+pub struct Error {
+  kind: ErrorKind,
+  severity: Severity,
+  msg: String,
+  pos: SourceSpan,
+  /// Other places in the source that explain the error
+  notes: Vec<(SourceSpan, String)>,
+  help: Option<String>,
+}
+
+impl Error {
+  pub fn new(kind: ErrorKind, msg: &str, pos: SourceSpan) -> Self {
+    Self {
+      kind,
+      severity: Severity::Error,
+      msg: msg.to_owned(),
+      pos,
+      notes: Vec::new(),
+      help: None,
+    }
+  }
+
+  pub fn warning(kind: ErrorKind, msg: &str, pos: SourceSpan) -> Self {
+    Self {
+      severity: Severity::Warning,
+      ..Self::new(kind, msg, pos)
+    }
+  }
+
+  /// Labels another span of the source
+  pub fn with_note(mut self, pos: SourceSpan, msg: &str) -> Self {
+    self.notes.push((pos, msg.to_owned()));
+    self
+  }
+
+  /// Suggests how to fix the error
+  pub fn with_help(mut self, msg: &str) -> Self {
+    self.help = Some(msg.to_owned());
+    self
+  }
+
+  pub fn kind(&self) -> ErrorKind {
+    self.kind
+  }
+
+  pub fn severity(&self) -> Severity {
+    self.severity
+  }
+
+  pub fn msg(&self) -> &str {
+    &self.msg
+  }
+
+  pub fn pos(&self) -> &SourceSpan {
+    &self.pos
+  }
+
+  pub fn notes(&self) -> &[(SourceSpan, String)] {
+    &self.notes
+  }
+
+  pub fn help(&self) -> Option<&str> {
+    self.help.as_deref()
+  }
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let label = match self.severity {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+    };
+    write!(
+      f,
+      "{}[{}]: {} at {}:{}",
+      label,
+      self.kind.code(),
+      self.msg,
+      self.pos.start_pos.line + 1,
+      self.pos.start_pos.column + 1
+    )
+  }
+}
+
+impl std::error::Error for Error {}
+
+/// Renders `error` (or warning) for display, underlining the offending source
+pub fn format_error(error: &Error, source: &str, color: bool) -> String {
+  let (label, highlight) = match error.severity {
+    Severity::Error => ("error", RED),
+    Severity::Warning => ("warning", YELLOW),
+  };
+
+  let mut out = String::new();
+  // Writing to a String cannot fail
+  writeln!(
+    out,
+    "{} {}",
+    style(
+      &format!("{}[{}]:", label, error.kind.code()),
+      highlight,
+      color
+    ),
+    style(error.kind.name(), BOLD, color),
+  )
+  .unwrap();
+  underline(
+    &mut out, source, &error.pos, '^', &error.msg, highlight, color,
+  );
+  for (pos, msg) in &error.notes {
+    underline(&mut out, source, pos, '-', msg, BOLD, color);
+  }
+  if let Some(help) = &error.help {
+    writeln!(out, "      {} {}", style("help:", BOLD, color), help).unwrap();
+  }
+  writeln!(
+    out,
+    "      run `caravel --explain {}` for more information",
+    error.kind.code()
+  )
+  .unwrap();
+
+  out
+}
+
+/// Writes the line containing `pos`, with `pos` underlined and labeled with `msg`
+fn underline(
+  out: &mut String,
+  source: &str,
+  pos: &SourceSpan,
+  marker: char,
+  msg: &str,
+  highlight: &'static str,
+  color: bool,
+) {
+  let start = pos.start_pos.offset.min(source.len());
+  let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+  let line_end = source[start..]
+    .find('\n')
+    .map_or(source.len(), |i| start + i);
+  let line = &source[line_start..line_end];
+
+  // Pad with the same whitespace as the source line so that
+  // the marker stays aligned when the line contains tabs
+  let padding: String = source[line_start..start]
+    .chars()
+    .map(|c| if c == '\t' { '\t' } else { ' ' })
+    .collect();
+  // Spans reaching past the end of the line are only underlined up to it
+  let underlined = source[start..pos.end().clamp(start, line_end)]
+    .chars()
+    .count()
+    .max(1);
+
+  writeln!(out, "      {}", line).unwrap();
+  writeln!(
+    out,
+    "      {}{} {}",
+    padding,
+    style(&marker.to_string().repeat(underlined), highlight, color),
+    style(msg, highlight, color)
+  )
+  .unwrap();
+}
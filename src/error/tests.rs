@@ -0,0 +1,60 @@
+use super::{format_error, Error, ErrorKind};
+use crate::lexer::lexer::Lexer;
+use crate::lexer::token::TokenKind;
+
+/// Undeclared variable error at the last identifier of `source`
+fn undeclared_last(source: &str) -> Error {
+  let tokens = match Lexer::new(source).lex() {
+    Ok(tokens) => tokens,
+    Err(_) => panic!("{:?} doesn't lex", source),
+  };
+  let (name, pos) = tokens
+    .into_iter()
+    .rev()
+    .find_map(|token| match token.kind {
+      TokenKind::Identifier(name) => Some((name, token.pos)),
+      _ => None,
+    })
+    .unwrap();
+  Error::new(
+    ErrorKind::UndeclaredVariable,
+    &format!("use of undeclared variable \"{}\"", name),
+    pos,
+  )
+}
+
+#[test]
+fn aligns_carets_after_tabs_and_non_ascii_characters() {
+  let source = "let a: float = 1\n\tlet é: float = a + b";
+  let error = undeclared_last(source);
+  assert_eq!(error.pos.start_pos.line, 1);
+  assert_eq!(error.pos.start_pos.column, 20);
+
+  // The tab is repeated so that the caret lines up at any tab width,
+  // and "é" takes a single column although it is two bytes long
+  assert_eq!(
+    format_error(&error, source, false),
+    "\
+error[E0003]: use of undeclared variable
+      \tlet é: float = a + b
+      \t                   ^ use of undeclared variable \"b\"
+      run `caravel --explain E0003` for more information
+"
+  );
+}
+
+#[test]
+fn underlines_non_ascii_identifiers_once_per_character() {
+  let source = "let né: float = 1\nné + ééé";
+  let error = undeclared_last(source);
+  assert_eq!(error.pos.start_pos.column, 5);
+  assert_eq!(
+    format_error(&error, source, false),
+    "\
+error[E0003]: use of undeclared variable
+      né + ééé
+           ^^^ use of undeclared variable \"ééé\"
+      run `caravel --explain E0003` for more information
+"
+  );
+}
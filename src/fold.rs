@@ -0,0 +1,185 @@
+use crate::analyzer::Types;
+use crate::error::{Error, ErrorKind};
+use crate::interpreter::parse_float;
+use crate::lexer::token::Token;
+use crate::parser::ast::*;
+use crate::parser::visit::*;
+use crate::position::token_position::TokenSpan;
+use crate::resolver::{Resolution, SymbolId};
+use crate::types::Type;
+use std::collections::HashMap;
+
+/// Evaluates arithmetic on constants at compile time, replacing it
+/// in `ast` with its value. Immutable variables initialized with
+/// a constant are constants too. Returns warnings for divisions
+/// and modulos whose divisor is always zero, which are left as is.
+/// The nodes it adds are recorded in `types` of an analyzed `ast`
+pub fn fold(
+  ast: &mut Ast,
+  tokens: &[Token],
+  resolution: &Resolution,
+  types: &mut Types,
+) -> Vec<Error> {
+  let mut folder = Folder {
+    tokens,
+    resolution,
+    types,
+    constants: HashMap::new(),
+    values: HashMap::new(),
+    warnings: Vec::new(),
+  };
+  folder.visit_block(ast, ast.root());
+  folder.warnings
+}
+
+struct Folder<'a> {
+  tokens: &'a [Token],
+  resolution: &'a Resolution,
+  types: &'a mut Types,
+  /// Values of the immutable variables assigned a constant so far
+  constants: HashMap<SymbolId, f64>,
+  /// Values of the constant nodes visited so far, by node index
+  values: HashMap<usize, f64>,
+  warnings: Vec<Error>,
+}
+
+impl<'a> Folder<'a> {
+  fn value<T>(&self, id: NodeId<T>) -> Option<f64> {
+    self.values.get(&id.index()).copied()
+  }
+
+  fn set_value<T>(&mut self, id: NodeId<T>, value: Option<f64>) {
+    if let Some(value) = value {
+      self.values.insert(id.index(), value);
+    }
+  }
+
+  /// Records the value of a node computed from constants, and the
+  /// literal to replace it with if it has one
+  fn computed<T>(&mut self, id: NodeId<T>, value: f64) -> Option<Leaf> {
+    // Values that can't be written as literals are still
+    // known, so that the expressions around them can be folded
+    if value.is_finite() {
+      self.values.insert(id.index(), value);
+    }
+    literal(value)
+  }
+
+  fn push<T: Kind>(&mut self, ast: &mut Ast, node: T, span: TokenSpan) -> NodeId<T> {
+    let id = ast.push(node, span);
+    self.types.set(id, Type::Float);
+    id
+  }
+}
+
+impl<'a> VisitorMut for Folder<'a> {
+  fn visit_expression(&mut self, ast: &mut Ast, expr: NodeId<Expression>) {
+    walk_expression_mut(self, ast, expr);
+    if let Expression::Term(term) = ast[expr] {
+      self.set_value(expr, self.value(term));
+    }
+  }
+
+  fn visit_assignment(&mut self, ast: &mut Ast, assig: NodeId<Assignment>) {
+    walk_assignment_mut(self, ast, assig);
+    let val = match ast[assig] {
+      Assignment::Initialization(_, _, _, None) => return,
+      Assignment::Initialization(_, _, _, Some(val)) | Assignment::Reassignment(_, val) => val,
+    };
+
+    let id = match self.resolution.symbol_at(ast.ident_token(assig)) {
+      Some(id) => id,
+      None => return,
+    };
+    // Immutable variables are assigned at most once
+    if let Some(val) = self.value(val) {
+      if self.resolution.declaration(id).mutability == Mutability::Immutable {
+        self.constants.insert(id, val);
+      }
+    }
+  }
+
+  fn visit_term(&mut self, ast: &mut Ast, term: NodeId<Term>) {
+    walk_term_mut(self, ast, term);
+    let value = match ast[term] {
+      Term::Factor(factor) => return self.set_value(term, self.value(factor)),
+      Term::Operation(lhs, op, rhs) => match (self.value(lhs), self.value(rhs)) {
+        (Some(lhs), Some(rhs)) => match op {
+          TermOp::Add => lhs + rhs,
+          TermOp::Subtract => lhs - rhs,
+        },
+        _ => return,
+      },
+    };
+
+    let span = ast.span(term);
+    if let Some(literal) = self.computed(term, value) {
+      let leaf = self.push(ast, literal, span);
+      let factor = self.push(ast, Factor::Leaf(leaf), span);
+      ast[term] = Term::Factor(factor);
+    }
+  }
+
+  fn visit_factor(&mut self, ast: &mut Ast, factor: NodeId<Factor>) {
+    walk_factor_mut(self, ast, factor);
+    let (lhs, op, rhs) = match ast[factor] {
+      Factor::Leaf(leaf) => return self.set_value(factor, self.value(leaf)),
+      Factor::Operation(lhs, op, rhs) => (lhs, op, rhs),
+    };
+
+    if self.value(rhs) == Some(0.0) && !matches!(op, FactorOp::Multiply) {
+      let msg = match op {
+        FactorOp::Divide => "division by zero",
+        _ => "modulo by zero",
+      };
+      self.warnings.push(Error::warning(
+        ErrorKind::DivisionByZero,
+        msg,
+        ast.span(rhs).as_source_span(self.tokens),
+      ));
+      return;
+    }
+
+    let value = match (self.value(lhs), self.value(rhs)) {
+      (Some(lhs), Some(rhs)) => match op {
+        FactorOp::Multiply => lhs * rhs,
+        FactorOp::Divide => lhs / rhs,
+        FactorOp::Modulo => lhs % rhs,
+      },
+      _ => return,
+    };
+
+    let span = ast.span(factor);
+    if let Some(literal) = self.computed(factor, value) {
+      let leaf = self.push(ast, literal, span);
+      ast[factor] = Factor::Leaf(leaf);
+    }
+  }
+
+  fn visit_leaf(&mut self, ast: &mut Ast, leaf: NodeId<Leaf>) {
+    walk_leaf_mut(self, ast, leaf);
+    let value = match &ast[leaf] {
+      Leaf::FloatLiteral(value) => return self.set_value(leaf, Some(parse_float(value))),
+      Leaf::Identifier(_) => self
+        .resolution
+        .uses
+        .get(&ast.span(leaf).start)
+        .and_then(|id| self.constants.get(id).copied()),
+      Leaf::Term(term) => self.value(*term),
+    };
+
+    if let Some(literal) = value.and_then(|value| self.computed(leaf, value)) {
+      ast[leaf] = literal;
+    }
+  }
+}
+
+/// Literal with the value `value`. Overflowing arithmetic is left
+/// for run time, and so are negative values (including -0), which
+/// have no literal
+fn literal(value: f64) -> Option<Leaf> {
+  match value.is_finite() && value.is_sign_positive() {
+    true => Some(Leaf::FloatLiteral(value.to_string())),
+    false => None,
+  }
+}
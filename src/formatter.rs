@@ -0,0 +1,234 @@
+use crate::error::Result;
+use crate::lexer::lexer::Lexer;
+use crate::lexer::token::{Token, TokenKind, Trivia, TriviaKind};
+use crate::parser::ast::*;
+use crate::parser::parser::Parser;
+
+const INDENT: &str = "  ";
+
+/// Regenerates `source` in canonical form: one space around binary
+/// operators and `=`, a space after `:`, two-space indentation
+/// inside blocks and at most one consecutive blank line.
+/// Comments are kept at the end of their line or on their own line
+pub fn format(source: &str) -> Result<String> {
+  let (tokens, trivia) = Lexer::new(source).lex_with_trivia()?;
+  let ast = Parser::new(&tokens).parse()?;
+
+  let mut formatter = Formatter {
+    ast: &ast,
+    tokens: &tokens,
+    trivia: &trivia,
+    next_token: 0,
+    last_token: 0,
+    next_trivia: 0,
+    last_line: 0,
+    at_block_start: true,
+    indent: 0,
+    out: String::new(),
+  };
+  formatter.program(ast.root());
+
+  Ok(formatter.out)
+}
+
+// The AST is walked in step with the token stream: every piece of
+// text emitted consumes the token it was parsed from. Token positions
+// then tell where comments and blank lines were in the source
+struct Formatter<'a> {
+  ast: &'a Ast,
+  tokens: &'a [Token],
+  trivia: &'a [Trivia],
+  next_token: usize,
+  /// Index of the token written last
+  last_token: usize,
+  next_trivia: usize,
+  /// Source line of the last token or comment written
+  last_line: usize,
+  /// Suppresses blank lines directly after a `{`
+  at_block_start: bool,
+  indent: usize,
+  out: String,
+}
+
+impl<'a> Formatter<'a> {
+  fn program(&mut self, program: NodeId<Block>) {
+    // Skip the brackets the lexer wraps the program in
+    self.next_token += 1;
+    self.statements(program);
+  }
+
+  fn statements(&mut self, block: NodeId<Block>) {
+    for &expr in &self.ast[block].expressions {
+      let first = self.peek();
+      self.comments_before(first.pos.start_pos.offset);
+      self.begin_line(first.pos.start_pos.line);
+      self.expression(expr);
+      self.end_line();
+    }
+
+    let closing = self.peek();
+    self.comments_before(closing.pos.start_pos.offset);
+  }
+
+  fn block(&mut self, block: NodeId<Block>) {
+    self.emit("{");
+
+    let closing = self.peek().pos.start_pos.offset;
+    if self.ast[block].expressions.is_empty() && !self.has_comment_before(closing) {
+      self.emit("}");
+      return;
+    }
+
+    self.end_line();
+    self.indent += 1;
+    self.at_block_start = true;
+    self.statements(block);
+    self.indent -= 1;
+
+    self.out += &INDENT.repeat(self.indent);
+    self.emit("}");
+  }
+
+  fn expression(&mut self, expr: NodeId<Expression>) {
+    match self.ast[expr] {
+      Expression::Assignment(assig) => self.assignment(assig),
+      Expression::Block(block) => self.block(block),
+      Expression::Term(term) => self.term(term),
+    }
+  }
+
+  fn assignment(&mut self, assig: NodeId<Assignment>) {
+    match &self.ast[assig] {
+      Assignment::Initialization(mutability, ident, type_ident, val) => {
+        self.emit("let ");
+        if *mutability == Mutability::Mutable {
+          self.emit("mut ");
+        }
+        self.emit(ident);
+        self.emit(": ");
+        self.emit(type_ident);
+        if let Some(val) = *val {
+          self.emit(" = ");
+          self.expression(val);
+        }
+      }
+      Assignment::Reassignment(ident, val) => {
+        self.emit(ident);
+        self.emit(" = ");
+        self.expression(*val);
+      }
+    }
+  }
+
+  fn term(&mut self, term: NodeId<Term>) {
+    match self.ast[term] {
+      Term::Factor(factor) => self.factor(factor),
+      Term::Operation(lhs, op, rhs) => {
+        self.term(lhs);
+        self.emit(match op {
+          TermOp::Add => " + ",
+          TermOp::Subtract => " - ",
+        });
+        self.factor(rhs);
+      }
+    }
+  }
+
+  fn factor(&mut self, factor: NodeId<Factor>) {
+    match self.ast[factor] {
+      Factor::Leaf(leaf) => self.leaf(leaf),
+      Factor::Operation(lhs, op, rhs) => {
+        self.factor(lhs);
+        self.emit(match op {
+          FactorOp::Multiply => " * ",
+          FactorOp::Divide => " / ",
+          FactorOp::Modulo => " % ",
+        });
+        self.leaf(rhs);
+      }
+    }
+  }
+
+  fn leaf(&mut self, leaf: NodeId<Leaf>) {
+    match &self.ast[leaf] {
+      Leaf::Identifier(value) | Leaf::FloatLiteral(value) => self.emit(value),
+      Leaf::Term(term) => {
+        self.emit("(");
+        self.term(*term);
+        self.emit(")");
+      }
+    }
+  }
+
+  // Helpers
+  /// Next token that isn't an Eol
+  fn peek(&mut self) -> &'a Token {
+    while let Some(Token {
+      kind: TokenKind::Eol,
+      ..
+    }) = self.tokens.get(self.next_token)
+    {
+      self.next_token += 1;
+    }
+    &self.tokens[self.next_token]
+  }
+
+  /// Writes `text` in place of the next token
+  fn emit(&mut self, text: &str) {
+    self.peek();
+    self.last_token = self.next_token;
+    self.next_token += 1;
+    self.out += text;
+  }
+
+  /// Indents a new line, keeping a single blank line
+  /// if there was at least one before `line` in the source
+  fn begin_line(&mut self, line: usize) {
+    if !self.at_block_start && line > self.last_line + 1 {
+      self.out.push('\n');
+    }
+    self.at_block_start = false;
+    self.out += &INDENT.repeat(self.indent);
+  }
+
+  /// Ends the line of the last emitted token,
+  /// moving a comment that followed it along
+  fn end_line(&mut self) {
+    let line = self.tokens[self.last_token].pos.start_pos.line;
+
+    if let Some(comment) = self.trivia.get(self.next_trivia) {
+      if comment.pos.start_pos.line == line {
+        self.out.push(' ');
+        self.comment(comment);
+      }
+    }
+
+    self.out.push('\n');
+    self.last_line = line;
+  }
+
+  fn has_comment_before(&self, offset: usize) -> bool {
+    match self.trivia.get(self.next_trivia) {
+      Some(comment) => comment.pos.start_pos.offset < offset,
+      None => false,
+    }
+  }
+
+  /// Writes the comments before `offset` on lines of their own
+  fn comments_before(&mut self, offset: usize) {
+    while self.has_comment_before(offset) {
+      let comment = &self.trivia[self.next_trivia];
+      self.begin_line(comment.pos.start_pos.line);
+      self.comment(comment);
+      self.out.push('\n');
+      self.last_line = comment.pos.start_pos.line;
+    }
+  }
+
+  fn comment(&mut self, comment: &Trivia) {
+    match &comment.kind {
+      TriviaKind::LineComment(text) => self.out += text.trim_end(),
+    }
+    self.next_trivia += 1;
+  }
+}
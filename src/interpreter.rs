@@ -0,0 +1,155 @@
+use crate::error::{Error, ErrorKind, Result};
+use crate::lexer::token::Token;
+use crate::parser::ast::*;
+use crate::symbol_table::SymbolTable;
+use std::fmt::{self, Display};
+
+/// Runtime value of an expression
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Value {
+  Float(f64),
+  Void,
+}
+
+impl Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Value::Float(value) => write!(f, "{}", value),
+      Value::Void => write!(f, "()"),
+    }
+  }
+}
+
+/// Values of all variables in scope, `None` until they are assigned
+#[derive(Clone)]
+pub struct Environment {
+  values: SymbolTable<Option<Value>>,
+}
+
+impl Default for Environment {
+  fn default() -> Self {
+    Self {
+      values: SymbolTable::new(None),
+    }
+  }
+}
+
+/// Tree-walking evaluator for analyzed programs
+pub struct Interpreter<'a> {
+  env: Environment,
+  tokens: &'a Vec<Token>,
+  ast: &'a Ast,
+}
+
+impl<'a> Interpreter<'a> {
+  pub fn new(tokens: &'a Vec<Token>, ast: &'a Ast) -> Self {
+    Self::with_environment(tokens, ast, Environment::default())
+  }
+
+  /// Evaluates with the variables of a previous run in scope
+  pub fn with_environment(tokens: &'a Vec<Token>, ast: &'a Ast, env: Environment) -> Self {
+    Self { env, tokens, ast }
+  }
+
+  pub fn into_environment(self) -> Environment {
+    self.env
+  }
+
+  pub fn run(&mut self) -> Result<Value> {
+    self.eval_block(self.ast.root())
+  }
+
+  fn eval_expression(&mut self, expr: NodeId<Expression>) -> Result<Value> {
+    match self.ast[expr] {
+      Expression::Assignment(assig) => self.eval_assignment(assig),
+      Expression::Block(block) => self.eval_block(block),
+      Expression::Term(term) => self.eval_term(term),
+    }
+  }
+
+  fn eval_block(&mut self, block: NodeId<Block>) -> Result<Value> {
+    let mut value = Value::Void;
+    for &expr in &self.ast[block].expressions {
+      value = self.eval_expression(expr)?;
+    }
+    Ok(value)
+  }
+
+  fn eval_assignment(&mut self, assig: NodeId<Assignment>) -> Result<Value> {
+    match &self.ast[assig] {
+      Assignment::Initialization(_, ident, _, None) => {
+        self.env.values.set(ident, None);
+        Ok(Value::Void)
+      }
+      Assignment::Initialization(_, ident, _, Some(val)) => {
+        let value = self.eval_expression(*val)?;
+        self.env.values.set(ident, Some(value));
+        Ok(value)
+      }
+      Assignment::Reassignment(ident, val) => {
+        let value = self.eval_expression(*val)?;
+        // The analyzer has checked that the variable is declared
+        *self.env.values.get_mut(ident).unwrap() = Some(value);
+        Ok(value)
+      }
+    }
+  }
+
+  fn eval_term(&self, term: NodeId<Term>) -> Result<Value> {
+    match self.ast[term] {
+      Term::Factor(factor) => self.eval_factor(factor),
+      Term::Operation(lhs, op, rhs) => {
+        let lhs = self.eval_term(lhs)?;
+        let rhs = self.eval_factor(rhs)?;
+        Ok(arithmetic(lhs, rhs, |lhs, rhs| match op {
+          TermOp::Add => lhs + rhs,
+          TermOp::Subtract => lhs - rhs,
+        }))
+      }
+    }
+  }
+
+  fn eval_factor(&self, factor: NodeId<Factor>) -> Result<Value> {
+    match self.ast[factor] {
+      Factor::Leaf(leaf) => self.eval_leaf(leaf),
+      Factor::Operation(lhs, op, rhs) => {
+        let lhs = self.eval_factor(lhs)?;
+        let rhs = self.eval_leaf(rhs)?;
+        Ok(arithmetic(lhs, rhs, |lhs, rhs| match op {
+          FactorOp::Multiply => lhs * rhs,
+          FactorOp::Divide => lhs / rhs,
+          FactorOp::Modulo => lhs % rhs,
+        }))
+      }
+    }
+  }
+
+  fn eval_leaf(&self, leaf: NodeId<Leaf>) -> Result<Value> {
+    match &self.ast[leaf] {
+      Leaf::FloatLiteral(value) => Ok(Value::Float(parse_float(value))),
+      Leaf::Identifier(ident) => match self.env.values.get(ident).flatten() {
+        Some(value) => Ok(value),
+        None => Err(Error::new(
+          ErrorKind::UninitializedVariable,
+          &format!(r#"use of uninitialized variable "{}""#, ident),
+          self.ast.span(leaf).as_source_span(self.tokens),
+        )),
+      },
+      Leaf::Term(term) => self.eval_term(*term),
+    }
+  }
+}
+
+/// Applies `op` to two floats. The analyzer only admits operands
+/// of equal types, and arithmetic on `void` yields `void`
+fn arithmetic(lhs: Value, rhs: Value, op: impl Fn(f64, f64) -> f64) -> Value {
+  match (lhs, rhs) {
+    (Value::Float(lhs), Value::Float(rhs)) => Value::Float(op(lhs, rhs)),
+    _ => Value::Void,
+  }
+}
+
+/// Value of a float literal as written in source, e.g. `1_000.5`
+pub fn parse_float(literal: &str) -> f64 {
+  literal.replace('_', "").parse().unwrap()
+}
@@ -0,0 +1,155 @@
+use super::*;
+use crate::interpreter::parse_float;
+use crate::parser::ast::*;
+use crate::resolver::Resolution;
+use std::convert::TryFrom;
+
+/// Lowers an analyzed program. Values of type `void` carry
+/// no data, so they get no register
+pub fn lower(ast: &Ast, resolution: &Resolution) -> Function {
+  let variables = resolution
+    .declarations
+    .iter()
+    .map(|declaration| Variable {
+      name: declaration.name.clone(),
+      // The analyzer has checked that the type exists
+      typ: Type::try_from(declaration.type_ident.clone()).unwrap(),
+    })
+    .collect();
+
+  let mut lowerer = Lowerer {
+    ast,
+    resolution,
+    function: Function {
+      ret: Type::Void,
+      registers: Vec::new(),
+      variables,
+      blocks: Vec::new(),
+    },
+    instructions: Vec::new(),
+  };
+
+  let result = lowerer.block(ast.root());
+  if let Some(result) = result {
+    lowerer.function.ret = lowerer.function.registers[result.0];
+  }
+  let instructions = lowerer.instructions;
+  lowerer.function.blocks.push(BasicBlock {
+    instructions,
+    terminator: Terminator::Return(result),
+  });
+  lowerer.function
+}
+
+struct Lowerer<'a> {
+  ast: &'a Ast,
+  resolution: &'a Resolution,
+  function: Function,
+  /// Instructions of the block being built
+  instructions: Vec<Instruction>,
+}
+
+impl<'a> Lowerer<'a> {
+  fn block(&mut self, block: NodeId<Block>) -> Option<Reg> {
+    let mut value = None;
+    for &expr in &self.ast[block].expressions {
+      value = self.expression(expr);
+    }
+    value
+  }
+
+  fn expression(&mut self, expr: NodeId<Expression>) -> Option<Reg> {
+    match self.ast[expr] {
+      Expression::Assignment(assig) => self.assignment(assig),
+      Expression::Block(block) => self.block(block),
+      Expression::Term(term) => self.term(term),
+    }
+  }
+
+  fn assignment(&mut self, assig: NodeId<Assignment>) -> Option<Reg> {
+    let val = match self.ast[assig] {
+      Assignment::Initialization(_, _, _, None) => return None,
+      Assignment::Initialization(_, _, _, Some(val)) | Assignment::Reassignment(_, val) => {
+        self.expression(val)?
+      }
+    };
+
+    // The analyzer has checked that every assigned variable is declared
+    let var = Var(
+      self
+        .resolution
+        .symbol_at(self.ast.ident_token(assig))
+        .unwrap()
+        .0,
+    );
+    self.instructions.push(Instruction::Store(var, val));
+    Some(val)
+  }
+
+  fn term(&mut self, term: NodeId<Term>) -> Option<Reg> {
+    match self.ast[term] {
+      Term::Factor(factor) => self.factor(factor),
+      Term::Operation(lhs, op, rhs) => {
+        let (lhs, rhs) = (self.term(lhs), self.factor(rhs));
+        let op = match op {
+          TermOp::Add => BinaryOp::Add,
+          TermOp::Subtract => BinaryOp::Subtract,
+        };
+        self.binary(op, lhs?, rhs?)
+      }
+    }
+  }
+
+  fn factor(&mut self, factor: NodeId<Factor>) -> Option<Reg> {
+    match self.ast[factor] {
+      Factor::Leaf(leaf) => self.leaf(leaf),
+      Factor::Operation(lhs, op, rhs) => {
+        let (lhs, rhs) = (self.factor(lhs), self.leaf(rhs));
+        let op = match op {
+          FactorOp::Multiply => BinaryOp::Multiply,
+          FactorOp::Divide => BinaryOp::Divide,
+          FactorOp::Modulo => BinaryOp::Modulo,
+        };
+        self.binary(op, lhs?, rhs?)
+      }
+    }
+  }
+
+  fn leaf(&mut self, leaf: NodeId<Leaf>) -> Option<Reg> {
+    match &self.ast[leaf] {
+      Leaf::FloatLiteral(value) => {
+        let dest = self.register(Type::Float);
+        self
+          .instructions
+          .push(Instruction::Const(dest, parse_float(value)));
+        Some(dest)
+      }
+      Leaf::Identifier(_) => {
+        let start = self.ast.span(leaf).start;
+        let var = Var(self.resolution.uses[&start].0);
+        let typ = self.function.variables[var.0].typ;
+        if typ == Type::Void {
+          return None;
+        }
+
+        let dest = self.register(typ);
+        self.instructions.push(Instruction::Load(dest, var));
+        Some(dest)
+      }
+      Leaf::Term(term) => self.term(*term),
+    }
+  }
+
+  fn binary(&mut self, op: BinaryOp, lhs: Reg, rhs: Reg) -> Option<Reg> {
+    let dest = self.register(self.function.registers[lhs.0]);
+    self
+      .instructions
+      .push(Instruction::Binary(dest, op, lhs, rhs));
+    Some(dest)
+  }
+
+  fn register(&mut self, typ: Type) -> Reg {
+    self.function.registers.push(typ);
+    Reg(self.function.registers.len() - 1)
+  }
+}
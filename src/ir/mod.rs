@@ -0,0 +1,148 @@
+//! Lowered form of a program shared by the code generators: basic
+//! blocks of instructions over typed virtual registers, each assigned
+//! exactly once, with variables only accessed through loads and stores
+
+pub mod lower;
+pub mod verify;
+
+use crate::types::Type;
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+/// Virtual register, an index into `Function::registers`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Reg(pub usize);
+
+/// Variable, an index into `Function::variables`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Var(pub usize);
+
+/// Basic block, an index into `Function::blocks`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct BlockId(pub usize);
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum BinaryOp {
+  Add,
+  Subtract,
+  Multiply,
+  Divide,
+  Modulo,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Instruction {
+  /// `dest = value`
+  Const(Reg, f64),
+  /// `dest = lhs op rhs`
+  Binary(Reg, BinaryOp, Reg, Reg),
+  /// `dest = var`
+  Load(Reg, Var),
+  /// `var = src`
+  Store(Var, Reg),
+}
+
+/// How control leaves a basic block
+#[derive(Clone, PartialEq, Debug)]
+pub enum Terminator {
+  Jump(BlockId),
+  /// Ends the function with its result, `None` for `void`
+  Return(Option<Reg>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BasicBlock {
+  pub instructions: Vec<Instruction>,
+  pub terminator: Terminator,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Variable {
+  pub name: String,
+  pub typ: Type,
+}
+
+/// A lowered program. Execution starts at the first block
+#[derive(Clone, PartialEq, Debug)]
+pub struct Function {
+  pub ret: Type,
+  /// Type of every register
+  pub registers: Vec<Type>,
+  pub variables: Vec<Variable>,
+  pub blocks: Vec<BasicBlock>,
+}
+
+impl Display for BinaryOp {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let name = match self {
+      Self::Add => "add",
+      Self::Subtract => "sub",
+      Self::Multiply => "mul",
+      Self::Divide => "div",
+      Self::Modulo => "rem",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+/// Textual form, one instruction per line:
+///
+/// ```text
+/// fn main() -> float {
+///   var @x: float
+///
+/// bb0:
+///   %0: float = const 2
+///   store @x, %0
+///   %1: float = load @x
+///   ret %1
+/// }
+/// ```
+impl Display for Function {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // Nothing stops the variables of a function from sharing a name
+    let mut seen = HashSet::new();
+    let duplicated: HashSet<&str> = self
+      .variables
+      .iter()
+      .filter(|var| !seen.insert(&var.name[..]))
+      .map(|var| &var.name[..])
+      .collect();
+    let var = |var: &Var| match &self.variables[var.0].name {
+      name if duplicated.contains(&name[..]) => format!("@{}.{}", name, var.0),
+      name => format!("@{}", name),
+    };
+    let reg = |reg: &Reg| format!("%{}: {}", reg.0, self.registers[reg.0]);
+
+    writeln!(f, "fn main() -> {} {{", self.ret)?;
+    for (i, variable) in self.variables.iter().enumerate() {
+      writeln!(f, "  var {}: {}", var(&Var(i)), variable.typ)?;
+    }
+
+    for (i, block) in self.blocks.iter().enumerate() {
+      if i > 0 || !self.variables.is_empty() {
+        writeln!(f)?;
+      }
+      writeln!(f, "bb{}:", i)?;
+
+      for instruction in &block.instructions {
+        match instruction {
+          Instruction::Const(dest, value) => writeln!(f, "  {} = const {}", reg(dest), value)?,
+          Instruction::Binary(dest, op, lhs, rhs) => {
+            writeln!(f, "  {} = {} %{}, %{}", reg(dest), op, lhs.0, rhs.0)?
+          }
+          Instruction::Load(dest, src) => writeln!(f, "  {} = load {}", reg(dest), var(src))?,
+          Instruction::Store(dest, src) => writeln!(f, "  store {}, %{}", var(dest), src.0)?,
+        }
+      }
+
+      match &block.terminator {
+        Terminator::Jump(target) => writeln!(f, "  jmp bb{}", target.0)?,
+        Terminator::Return(Some(value)) => writeln!(f, "  ret %{}", value.0)?,
+        Terminator::Return(None) => writeln!(f, "  ret")?,
+      }
+    }
+
+    writeln!(f, "}}")
+  }
+}
@@ -0,0 +1,114 @@
+use super::*;
+
+/// Checks the invariants that code generators rely on: every register
+/// is assigned exactly once, before it is used (in block order), and
+/// with its declared type; operands, variables and jump targets exist;
+/// the returned value has the function's type
+pub fn verify(function: &Function) -> Result<(), String> {
+  if function.blocks.is_empty() {
+    return Err("function has no blocks".to_owned());
+  }
+
+  let mut verifier = Verifier {
+    function,
+    defined: vec![false; function.registers.len()],
+  };
+
+  for (i, block) in function.blocks.iter().enumerate() {
+    for (n, instruction) in block.instructions.iter().enumerate() {
+      verifier
+        .instruction(instruction)
+        .map_err(|msg| format!("bb{}, instruction {}: {}", i, n, msg))?;
+    }
+    verifier
+      .terminator(&block.terminator)
+      .map_err(|msg| format!("bb{}, terminator: {}", i, msg))?;
+  }
+
+  Ok(())
+}
+
+struct Verifier<'a> {
+  function: &'a Function,
+  /// Registers assigned so far
+  defined: Vec<bool>,
+}
+
+impl<'a> Verifier<'a> {
+  fn instruction(&mut self, instruction: &Instruction) -> Result<(), String> {
+    match instruction {
+      Instruction::Const(dest, _) => self.define(*dest, Type::Float),
+      Instruction::Binary(dest, _, lhs, rhs) => {
+        let typ = self.register_type(*lhs)?;
+        self.use_register(*lhs, typ)?;
+        self.use_register(*rhs, typ)?;
+        self.define(*dest, typ)
+      }
+      Instruction::Load(dest, var) => {
+        let typ = self.variable_type(*var)?;
+        self.define(*dest, typ)
+      }
+      Instruction::Store(var, src) => {
+        let typ = self.variable_type(*var)?;
+        self.use_register(*src, typ)
+      }
+    }
+  }
+
+  fn terminator(&self, terminator: &Terminator) -> Result<(), String> {
+    match terminator {
+      Terminator::Jump(target) if target.0 >= self.function.blocks.len() => {
+        Err(format!("jump to missing block bb{}", target.0))
+      }
+      Terminator::Return(Some(value)) => self.use_register(*value, self.function.ret),
+      Terminator::Return(None) if self.function.ret != Type::Void => {
+        Err(format!("missing {} return value", self.function.ret))
+      }
+      _ => Ok(()),
+    }
+  }
+
+  fn define(&mut self, reg: Reg, typ: Type) -> Result<(), String> {
+    let reg_type = self.register_type(reg)?;
+    if reg_type != typ {
+      return Err(format!(
+        "%{} has type {}, but is assigned a {}",
+        reg.0, reg_type, typ
+      ));
+    }
+    if self.defined[reg.0] {
+      return Err(format!("%{} is assigned twice", reg.0));
+    }
+    self.defined[reg.0] = true;
+    Ok(())
+  }
+
+  /// Checks that `reg` holds a value of type `typ` at this point
+  fn use_register(&self, reg: Reg, typ: Type) -> Result<(), String> {
+    let reg_type = self.register_type(reg)?;
+    if !self.defined[reg.0] {
+      return Err(format!("%{} is used before it is assigned", reg.0));
+    }
+    if reg_type != typ {
+      return Err(format!(
+        "%{} has type {}, where a {} is expected",
+        reg.0, reg_type, typ
+      ));
+    }
+    Ok(())
+  }
+
+  fn register_type(&self, reg: Reg) -> Result<Type, String> {
+    match self.function.registers.get(reg.0) {
+      Some(typ) => Ok(*typ),
+      None => Err(format!("missing register %{}", reg.0)),
+    }
+  }
+
+  fn variable_type(&self, var: Var) -> Result<Type, String> {
+    match self.function.variables.get(var.0) {
+      Some(variable) => Ok(variable.typ),
+      None => Err(format!("missing variable {}", var.0)),
+    }
+  }
+}
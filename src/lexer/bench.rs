@@ -0,0 +1,40 @@
+//! Lexes synthetic programs of increasing size. Time per
+//! iteration should grow linearly with the number of lines
+//!
+//! ```text
+//! $ cargo bench lexer
+//! ```
+
+extern crate test;
+
+use super::lexer::Lexer;
+use test::{black_box, Bencher};
+
+fn synthetic_program(lines: usize) -> String {
+  let mut source = String::from("let v0: float = 1\n");
+  for i in 1..lines {
+    source += &format!("let v{}: float = (v{} + 3.25) * 2 % 7 - 1_000\n", i, i - 1);
+  }
+  source
+}
+
+fn bench_lines(b: &mut Bencher, lines: usize) {
+  let source = synthetic_program(lines);
+  b.bytes = source.len() as u64;
+  b.iter(|| Lexer::new(black_box(&source)).lex().ok());
+}
+
+#[bench]
+fn lex_1_000_lines(b: &mut Bencher) {
+  bench_lines(b, 1_000);
+}
+
+#[bench]
+fn lex_10_000_lines(b: &mut Bencher) {
+  bench_lines(b, 10_000);
+}
+
+#[bench]
+fn lex_100_000_lines(b: &mut Bencher) {
+  bench_lines(b, 100_000);
+}
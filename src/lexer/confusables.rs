@@ -0,0 +1,46 @@
+use super::token::{Token, TokenKind};
+use crate::error::{Error, ErrorKind};
+use std::collections::{HashMap, HashSet};
+use unicode_security::{skeleton, MixedScript};
+
+/// Warns about identifiers that could be mistaken for other ones,
+/// following the checks of UTS #39 (https://unicode.org/reports/tr39):
+/// identifiers mixing several scripts, and distinct identifiers
+/// that share a confusable skeleton. Pairs of ASCII identifiers such
+/// as `rn` and `m` are left alone, as UTS #39 suggests
+pub fn check(tokens: &[Token]) -> Vec<Error> {
+  let mut warnings = Vec::new();
+  // Skeleton -> first identifier seen with it
+  let mut skeletons: HashMap<String, &str> = HashMap::new();
+  // Each identifier is only reported once, at its first occurrence
+  let mut reported: HashSet<&str> = HashSet::new();
+
+  for token in tokens {
+    let ident = match &token.kind {
+      TokenKind::Identifier(ident) => &ident[..],
+      _ => continue,
+    };
+
+    let msg = if !ident.is_single_script() {
+      format!(
+        r#"identifier "{}" mixes characters from multiple scripts"#,
+        ident
+      )
+    } else {
+      match *skeletons.entry(skeleton(ident).collect()).or_insert(ident) {
+        first if first == ident || first.is_ascii() && ident.is_ascii() => continue,
+        first => format!(r#"identifier "{}" looks like "{}""#, ident, first),
+      }
+    };
+
+    if reported.insert(ident) {
+      warnings.push(Error::warning(
+        ErrorKind::ConfusableIdentifier,
+        &msg,
+        token.pos.clone(),
+      ));
+    }
+  }
+
+  warnings
+}
@@ -0,0 +1,59 @@
+use crate::position::source_position::SourcePosition;
+use std::str::Chars;
+
+/// Character-by-character view of the input. Every operation
+/// is O(1) apart from `peek_nth`, which is O(n) in its lookahead
+pub struct Cursor<'a> {
+  chars: Chars<'a>,
+  pos: SourcePosition,
+}
+
+/// Returned by `peek` and `bump` once the input is exhausted
+pub const EOF_CHAR: char = '\0';
+
+impl<'a> Cursor<'a> {
+  pub fn new(input: &'a str) -> Self {
+    Self {
+      chars: input.chars(),
+      pos: SourcePosition::start(),
+    }
+  }
+
+  /// Position of the next character
+  pub fn pos(&self) -> &SourcePosition {
+    &self.pos
+  }
+
+  pub fn is_eof(&self) -> bool {
+    self.chars.as_str().is_empty()
+  }
+
+  /// Next character, without consuming it
+  pub fn peek(&self) -> char {
+    self.peek_nth(0)
+  }
+
+  /// Character `n` places after the next one, without consuming anything
+  pub fn peek_nth(&self, n: usize) -> char {
+    self.chars.clone().nth(n).unwrap_or(EOF_CHAR)
+  }
+
+  /// Consumes the next character
+  pub fn bump(&mut self) -> char {
+    match self.chars.next() {
+      Some(c) => {
+        self.pos.advance(c);
+        c
+      }
+      None => EOF_CHAR,
+    }
+  }
+
+  /// Consumes characters while `predicate` holds,
+  /// appending them to `value`
+  pub fn bump_while(&mut self, value: &mut String, predicate: impl Fn(char) -> bool) {
+    while !self.is_eof() && predicate(self.peek()) {
+      value.push(self.bump());
+    }
+  }
+}
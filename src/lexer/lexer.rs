@@ -1,35 +1,48 @@
-use super::token::{Token, TokenKind};
+use super::cursor::Cursor;
+use super::token::{Token, TokenKind, Trivia, TriviaKind};
 use super::util;
 use crate::error::{Error, ErrorKind, Result};
 use crate::position::source_position::{SourcePosition, SourceSpan};
+use unicode_normalization::UnicodeNormalization;
 
-pub struct Lexer {
-  input: String,
-  pos: SourcePosition,
+pub struct Lexer<'a> {
+  cursor: Cursor<'a>,
   tokens: Vec<Token>,
+  trivia: Vec<Trivia>,
 }
 
-impl Lexer {
-  pub fn new(input: &str) -> Self {
+impl<'a> Lexer<'a> {
+  pub fn new(input: &'a str) -> Self {
     Self {
-      input: input.to_owned(),
-      pos: SourcePosition::start(),
+      cursor: Cursor::new(input),
       tokens: Vec::new(),
+      trivia: Vec::new(),
     }
   }
 
-  pub fn lex(mut self) -> Result<Vec<Token>> {
+  pub fn lex(self) -> Result<Vec<Token>> {
+    Ok(self.lex_with_trivia()?.0)
+  }
+
+  /// Like `lex`, but also returns the comments
+  /// skipped in between tokens, in source order
+  pub fn lex_with_trivia(mut self) -> Result<(Vec<Token>, Vec<Trivia>)> {
     // Surround with brackets so that the program
     // is parsed as an entire block
-    self.add_token(TokenKind::LBracket, self.pos.clone());
+    self.add_token(TokenKind::LBracket, self.cursor.pos().clone());
 
-    while self.pos.index < self.input.len() {
-      let start_pos = self.pos.clone();
-      let c = self.get();
+    while !self.cursor.is_eof() {
+      let start_pos = self.cursor.pos().clone();
+      let c = self.cursor.peek();
 
       // Ignore whitespace
       if c == ' ' || c == '\t' {
-        self.eat();
+        self.cursor.bump();
+        continue;
+      }
+
+      if c == '/' && self.cursor.peek_nth(1) == '/' {
+        self.parse_line_comment(start_pos);
         continue;
       }
 
@@ -38,7 +51,7 @@ impl Lexer {
       } else if c.is_ascii_digit() {
         self.parse_float_literal()
       } else {
-        match self.eat() {
+        match self.cursor.bump() {
           '\n' => TokenKind::Eol,
           '+' => TokenKind::Add,
           '-' => TokenKind::Subtract,
@@ -55,10 +68,7 @@ impl Lexer {
             return Err(Error::new(
               ErrorKind::UnexpectedChar,
               &format!("Unexpected character '{}'", c),
-              SourceSpan {
-                start_pos,
-                source_len: 1,
-              },
+              SourceSpan::new(start_pos, c.len_utf8()),
             ))
           }
         }
@@ -69,64 +79,55 @@ impl Lexer {
 
     // Surround with brackets so that the program
     // is parsed as an entire block
-    self.add_token(TokenKind::RBracket, self.pos.clone());
+    self.add_token(TokenKind::RBracket, self.cursor.pos().clone());
 
-    Ok(self.tokens)
+    Ok((self.tokens, self.trivia))
   }
 
   // Helpers
-  fn get(&self) -> char {
-    self
-      .input
-      .chars()
-      .nth(self.pos.index as usize)
-      .unwrap_or('\0')
-  }
-
-  fn eat(&mut self) -> char {
-    let c = self.get();
-
-    if c == '\n' {
-      self.pos.advance_ln();
-    } else {
-      self.pos.advance_col();
-    }
-
-    c
-  }
-
   fn add_token(&mut self, kind: TokenKind, start_pos: SourcePosition) {
+    let len = self.cursor.pos().offset - start_pos.offset;
     self.tokens.push(Token {
       kind,
-      pos: SourceSpan {
-        start_pos: start_pos.clone(),
-        source_len: self.pos.index - start_pos.index,
-      },
+      pos: SourceSpan::new(start_pos, len),
     })
   }
 
   // Token specific parse functions
+  fn parse_line_comment(&mut self, start_pos: SourcePosition) {
+    let mut text = String::new();
+    // The newline is left to become an Eol token
+    self.cursor.bump_while(&mut text, |c| c != '\n');
+
+    let len = self.cursor.pos().offset - start_pos.offset;
+    self.trivia.push(Trivia {
+      kind: TriviaKind::LineComment(text),
+      pos: SourceSpan::new(start_pos, len),
+    });
+  }
+
   fn parse_identifier_or_keyword(&mut self) -> TokenKind {
-    let mut value = self.eat().to_string();
+    let mut value = self.cursor.bump().to_string();
+    self.cursor.bump_while(&mut value, util::is_ident_body);
 
-    while util::is_ident_body(self.get()) {
-      value.push(self.eat());
-    }
+    // Identifiers that only differ in their
+    // encoding must refer to the same symbol
+    let value: String = value.nfc().collect();
 
     match &value[..] {
       "let" => TokenKind::Let,
+      "mut" => TokenKind::Mut,
       _ => TokenKind::Identifier(value),
     }
   }
 
   fn parse_float_literal(&mut self) -> TokenKind {
-    let mut value = self.eat().to_string();
+    let mut value = self.cursor.bump().to_string();
     let mut encountered_period = false;
 
-    while util::is_float_literal_body(self.get()) {
-      let c = self.eat();
-
-      if c == '.' {
+    while util::is_float_literal_body(self.cursor.peek()) {
+      // A second period ends the literal and is left for the next token
+      if self.cursor.peek() == '.' {
         if encountered_period {
           break;
         }
@@ -134,7 +135,7 @@ impl Lexer {
         encountered_period = true;
       }
 
-      value.push(c);
+      value.push(self.cursor.bump());
     }
 
     TokenKind::FloatLiteral(value)
@@ -1,3 +1,10 @@
+#[cfg(test)]
+mod bench;
+pub mod confusables;
+pub mod cursor;
+#[allow(clippy::module_inception)]
 pub mod lexer;
+#[cfg(test)]
+mod tests;
 pub mod token;
 pub mod util;
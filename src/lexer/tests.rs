@@ -0,0 +1,182 @@
+use super::confusables;
+use super::cursor::{Cursor, EOF_CHAR};
+use super::lexer::Lexer;
+use super::token::{Token, TokenKind};
+use crate::error::{format_error, Error, Severity};
+
+fn lex(source: &str) -> Vec<Token> {
+  match Lexer::new(source).lex() {
+    Ok(tokens) => tokens,
+    Err(_) => panic!("{:?} doesn't lex", source),
+  }
+}
+
+fn lex_error(source: &str) -> Error {
+  match Lexer::new(source).lex() {
+    Ok(_) => panic!("{:?} lexes", source),
+    Err(error) => error,
+  }
+}
+
+/// Kinds of the tokens of `source`, without the brackets around the program
+fn kinds(source: &str) -> Vec<String> {
+  let tokens = lex(source);
+  tokens[1..tokens.len() - 1]
+    .iter()
+    .map(|token| format!("{:?}", token.kind))
+    .collect()
+}
+
+#[test]
+fn cursor_tracks_positions_and_stops_at_eof() {
+  let mut cursor = Cursor::new("aé\nb");
+  assert_eq!(cursor.peek_nth(1), 'é');
+  assert_eq!(cursor.bump(), 'a');
+  assert_eq!(cursor.bump(), 'é');
+  assert_eq!(cursor.pos().offset, 3);
+  assert_eq!(cursor.pos().column, 2);
+
+  cursor.bump();
+  assert_eq!((cursor.pos().line, cursor.pos().column), (1, 0));
+
+  let mut rest = String::new();
+  cursor.bump_while(&mut rest, |_| true);
+  assert_eq!(rest, "b");
+  assert!(cursor.is_eof());
+  assert_eq!(cursor.peek(), EOF_CHAR);
+  assert_eq!(cursor.bump(), EOF_CHAR);
+  assert_eq!(cursor.pos().offset, 5);
+}
+
+#[test]
+fn lexes_numbers_with_one_period() {
+  assert_eq!(
+    kinds("1 2.5 3. 1_000"),
+    [
+      r#"FloatLiteral("1")"#,
+      r#"FloatLiteral("2.5")"#,
+      r#"FloatLiteral("3.")"#,
+      r#"FloatLiteral("1_000")"#
+    ]
+  );
+}
+
+#[test]
+fn leaves_a_second_period_for_the_next_token() {
+  // It used to be consumed and silently dropped,
+  // so that "1.2.3" lexed as "1.2" followed by "3"
+  let source = "1.2.3";
+  assert_eq!(
+    format_error(&lex_error(source), source, false),
+    "\
+error[E0001]: unexpected character
+      1.2.3
+         ^ Unexpected character '.'
+      run `caravel --explain E0001` for more information
+"
+  );
+}
+
+#[test]
+fn ends_tokens_at_eof() {
+  assert!(kinds("").is_empty());
+  assert_eq!(kinds("x"), [r#"Identifier("x")"#]);
+  assert_eq!(kinds("12"), [r#"FloatLiteral("12")"#]);
+  assert_eq!(kinds("x\n"), [r#"Identifier("x")"#, "Eol"]);
+
+  // The closing bracket sits at the end of the input
+  let tokens = lex("ab ");
+  let last = tokens.last().unwrap();
+  assert_eq!((last.pos.start_pos.offset, last.pos.len), (3, 0));
+}
+
+#[test]
+fn lexes_large_inputs() {
+  // How the time taken grows with the input is measured by the benchmark
+  let lines = 10_000;
+  let source = "let v: float = (v + 3.25) * 2 % 7 - 1_000\n".repeat(lines);
+  let tokens = lex(&source);
+  assert_eq!(tokens.len(), lines * 17 + 2);
+  let last = tokens.last().unwrap();
+  assert_eq!(last.pos.start_pos.offset, source.len());
+  assert_eq!(last.pos.start_pos.line, lines);
+}
+
+/// Confusable identifier warnings for `source`, rendered
+fn confusables(source: &str) -> Vec<String> {
+  confusables::check(&lex(source))
+    .iter()
+    .map(|warning| {
+      assert_eq!(warning.severity(), Severity::Warning);
+      format_error(warning, source, false)
+    })
+    .collect()
+}
+
+#[test]
+fn warns_about_mixed_script_identifiers_once() {
+  // The "о" is Cyrillic
+  assert_eq!(
+    confusables("let tоtal: float = 1\ntоtal * 2"),
+    ["\
+warning[E0006]: confusable identifier
+      let tоtal: float = 1
+          ^^^^^ identifier \"tоtal\" mixes characters from multiple scripts
+      run `caravel --explain E0006` for more information
+"]
+  );
+}
+
+#[test]
+fn warns_about_identifiers_sharing_a_skeleton() {
+  // The second name is entirely Cyrillic
+  assert_eq!(
+    confusables("let scope: float = 1\nlet ѕсоре: float = 2\nscope + ѕсоре"),
+    ["\
+warning[E0006]: confusable identifier
+      let ѕсоре: float = 2
+          ^^^^^ identifier \"ѕсоре\" looks like \"scope\"
+      run `caravel --explain E0006` for more information
+"]
+  );
+}
+
+#[test]
+fn does_not_warn_about_distinct_identifiers() {
+  assert!(confusables("let größe: float = 1\nlet total: float = größe\ntotal").is_empty());
+  assert!(confusables("let 変数: float = 2\nlet _δ1: float = 変数 * 3\n_δ1").is_empty());
+}
+
+#[test]
+fn does_not_compare_ascii_identifiers() {
+  // Their skeletons are "xl" and "m", but so many ASCII names
+  // look alike that warning about them would only be noise
+  assert!(confusables("let x1: float = 1\nlet xl: float = x1\nxl").is_empty());
+  assert!(confusables("let rn: float = 1\nlet m: float = rn\nm").is_empty());
+}
+
+#[test]
+fn normalizes_identifiers_to_nfc() {
+  // Precomposed "é", then "e" followed by a combining acute accent
+  let source = "let caf\u{e9}: float = 1\ncafe\u{301} * 2";
+  assert!(confusables(source).is_empty());
+
+  let names: Vec<_> = lex(source)
+    .into_iter()
+    .filter_map(|token| match token.kind {
+      TokenKind::Identifier(name) if name != "float" => Some(name),
+      _ => None,
+    })
+    .collect();
+  assert_eq!(names, ["caf\u{e9}", "caf\u{e9}"]);
+}
+
+#[test]
+fn rejects_characters_outside_identifiers() {
+  for (source, column) in [("let a€: float = 1", 5), ("let 🙂: float = 1", 4)] {
+    let rendered = format_error(&lex_error(source), source, false);
+    let caret = format!("\n      {}^ Unexpected character", " ".repeat(column));
+    assert!(rendered.starts_with("error[E0001]"), "{}", rendered);
+    assert!(rendered.contains(&caret), "{}", rendered);
+  }
+}
@@ -6,7 +6,7 @@ pub struct Token {
   pub kind: TokenKind,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub enum TokenKind {
   Identifier(String),
 
@@ -32,9 +32,50 @@ pub enum TokenKind {
 
   // Keywords
   Let,
+  Mut,
 
   /// Special token, used by the parser to signify
   /// the end of the token stream. Will never be
   /// generated by the lexer
   Eof,
 }
+
+impl TokenKind {
+  /// Name of the variant, without its contents
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Identifier(_) => "Identifier",
+      Self::FloatLiteral(_) => "FloatLiteral",
+      Self::Add => "Add",
+      Self::Subtract => "Subtract",
+      Self::Multiply => "Multiply",
+      Self::Divide => "Divide",
+      Self::Modulo => "Modulo",
+      Self::Assignment => "Assignment",
+      Self::Eol => "Eol",
+      Self::LParen => "LParen",
+      Self::RParen => "RParen",
+      Self::RBracket => "RBracket",
+      Self::LBracket => "LBracket",
+      Self::Colon => "Colon",
+      Self::Let => "Let",
+      Self::Mut => "Mut",
+      Self::Eof => "Eof",
+    }
+  }
+}
+
+/// Source text that carries no meaning for the parser, but
+/// that tools such as the formatter need to preserve
+#[derive(Clone, Debug)]
+pub struct Trivia {
+  pub pos: SourceSpan,
+  pub kind: TriviaKind,
+}
+
+#[derive(Clone, Debug)]
+pub enum TriviaKind {
+  /// `// ...` up to, but excluding, the end of the line.
+  /// Holds the whole comment, including the slashes
+  LineComment(String),
+}
@@ -1,9 +1,13 @@
+use unicode_xid::UnicodeXID;
+
+// Identifiers follow UAX #31 (https://unicode.org/reports/tr31),
+// with '_' additionally allowed at the start
 pub fn is_ident_start(c: char) -> bool {
-  c.is_ascii_alphabetic() || c == '_'
+  c.is_xid_start() || c == '_'
 }
 
 pub fn is_ident_body(c: char) -> bool {
-  is_ident_start(c) || c.is_ascii_digit()
+  c.is_xid_continue()
 }
 
 pub fn is_float_literal_body(c: char) -> bool {
@@ -0,0 +1,43 @@
+#![feature(never_type)]
+#![cfg_attr(test, feature(test))]
+
+//! The Caravel compiler as a library. `compile` runs the whole
+//! pipeline; the individual stages (`Lexer`, `Parser`, `Analyzer`)
+//! can also be driven directly. Nothing in this crate writes to
+//! stdout or stderr: diagnostics and output are returned to the caller
+//!
+//! ```
+//! use caravel::{compile, CompileOptions, Type};
+//!
+//! let output = compile("let x: float = 2\nx * 3", &CompileOptions::default());
+//! assert!(output.succeeded());
+//! assert_eq!(output.ty, Some(Type::Float));
+//! ```
+
+pub mod analyzer;
+pub mod ansi;
+pub mod backend;
+pub mod bytecode;
+pub mod driver;
+pub mod error;
+pub mod fold;
+pub mod formatter;
+pub mod interpreter;
+pub mod ir;
+pub mod lexer;
+pub mod lsp;
+pub mod parser;
+pub mod position;
+pub mod rename;
+pub mod repl;
+pub mod resolver;
+pub mod symbol_table;
+pub mod types;
+pub mod unused;
+
+pub use analyzer::Analyzer;
+pub use driver::{compile, CompileOptions, CompileOutput, Emit};
+pub use error::{format_error, Error, ErrorKind, Result, Severity};
+pub use lexer::lexer::Lexer;
+pub use parser::{ast, parser::Parser, visit};
+pub use types::Type;
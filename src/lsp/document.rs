@@ -0,0 +1,114 @@
+use crate::driver::{compile, CompileOptions, Emit};
+use crate::error::Error;
+use crate::lexer::token::{Token, TokenKind};
+use crate::position::source_position::SourceSpan;
+use crate::resolver::Resolution;
+use crate::types::Type;
+use std::convert::TryFrom;
+
+/// A variable declaration
+pub struct Definition {
+  pub name: String,
+  /// `None` if the declared type doesn't exist
+  pub typ: Option<Type>,
+  /// Index of the declared identifier's token
+  pub token: usize,
+}
+
+/// An open text document and everything known about it
+pub struct Document {
+  pub text: String,
+  pub diagnostics: Vec<Error>,
+  /// Indexed by `SymbolId`
+  pub definitions: Vec<Definition>,
+  tokens: Vec<Token>,
+  resolution: Resolution,
+}
+
+impl Document {
+  pub fn new(text: String) -> Self {
+    let options = CompileOptions {
+      emit: Emit::Nothing,
+      ..CompileOptions::default()
+    };
+    let output = compile(&text, &options);
+    let tokens = output.tokens.unwrap_or_default();
+    let resolution = output.resolution.unwrap_or_default();
+
+    let definitions = resolution
+      .declarations
+      .iter()
+      .map(|declaration| Definition {
+        name: declaration.name.clone(),
+        typ: Type::try_from(declaration.type_ident.clone()).ok(),
+        token: declaration.token,
+      })
+      .collect();
+
+    Self {
+      text,
+      diagnostics: output.diagnostics,
+      definitions,
+      tokens,
+      resolution,
+    }
+  }
+
+  pub fn token_span(&self, token: usize) -> &SourceSpan {
+    &self.tokens[token].pos
+  }
+
+  /// Identifier token at `offset` and the definition it refers to.
+  /// A cursor right after an identifier is still on it
+  pub fn reference_at(&self, offset: usize) -> Option<(usize, &Definition)> {
+    let token = self.tokens.iter().position(|token| {
+      matches!(token.kind, TokenKind::Identifier(_))
+        && token.pos.start_pos.offset <= offset
+        && offset <= token.pos.end()
+    })?;
+    let id = self.resolution.symbol_at(token)?;
+    Some((token, &self.definitions[id.0]))
+  }
+
+  /// Definitions that can be referred to at `offset`,
+  /// which are those declared before it
+  pub fn visible_at(&self, offset: usize) -> Vec<&Definition> {
+    self
+      .definitions
+      .iter()
+      .filter(|definition| self.token_span(definition.token).end() <= offset)
+      .collect()
+  }
+
+  /// Converts an LSP position (line and UTF-16 code unit) to a byte offset
+  pub fn offset(&self, line: usize, character: usize) -> usize {
+    let line_start = match line {
+      0 => 0,
+      _ => match self.text.match_indices('\n').nth(line - 1) {
+        Some((i, _)) => i + 1,
+        None => return self.text.len(),
+      },
+    };
+
+    let mut units = 0;
+    for (i, c) in self.text[line_start..].char_indices() {
+      if units >= character || c == '\n' {
+        return line_start + i;
+      }
+      units += c.len_utf16();
+    }
+    self.text.len()
+  }
+
+  /// Converts a byte offset to an LSP position (line and UTF-16 code unit)
+  pub fn position(&self, offset: usize) -> (usize, usize) {
+    let offset = offset.min(self.text.len());
+    let before = &self.text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    (
+      before.matches('\n').count(),
+      before[line_start..].encode_utf16().count(),
+    )
+  }
+}
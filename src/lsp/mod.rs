@@ -0,0 +1,354 @@
+//! Language Server Protocol server
+//! (https://microsoft.github.io/language-server-protocol).
+//! `serve` speaks JSON-RPC over any pair of streams, `Server`
+//! handles decoded messages one at a time
+
+pub mod document;
+
+use crate::error::{Error, Severity};
+use crate::position::source_position::SourceSpan;
+use crate::rename::rename;
+use document::Document;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INVALID_REQUEST: i64 = -32600;
+const REQUEST_FAILED: i64 = -32803;
+
+// LSP enumerations
+const SYNC_FULL: u8 = 1;
+const COMPLETION_KIND_VARIABLE: u8 = 6;
+const SYMBOL_KIND_VARIABLE: u8 = 13;
+
+#[derive(Default)]
+pub struct Server {
+  documents: HashMap<String, Document>,
+  shutdown: bool,
+  exited: bool,
+}
+
+impl Server {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Whether the client has sent `exit`
+  pub fn exited(&self) -> bool {
+    self.exited
+  }
+
+  /// Handles one request or notification, returning the
+  /// responses and notifications to send to the client
+  pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+    let method = message["method"].as_str().unwrap_or("");
+    let params = &message["params"];
+
+    let id = match message.get("id") {
+      Some(id) => id,
+      None => return self.notification(method, params),
+    };
+
+    if self.shutdown && method != "exit" {
+      return vec![error_response(
+        id,
+        INVALID_REQUEST,
+        "server is shutting down",
+      )];
+    }
+
+    let result = match method {
+      "initialize" => Ok(json!({
+        "capabilities": {
+          "textDocumentSync": SYNC_FULL,
+          "hoverProvider": true,
+          "definitionProvider": true,
+          "completionProvider": {},
+          "documentSymbolProvider": true,
+          "renameProvider": true,
+        },
+        "serverInfo": { "name": "caravel", "version": env!("CARGO_PKG_VERSION") },
+      })),
+      "shutdown" => {
+        self.shutdown = true;
+        Ok(Value::Null)
+      }
+      "textDocument/hover" => self.hover(params),
+      "textDocument/definition" => self.definition(params),
+      "textDocument/completion" => self.completion(params),
+      "textDocument/documentSymbol" => self.document_symbols(params),
+      "textDocument/rename" => self.rename(params),
+      _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+    };
+
+    vec![match result {
+      Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+      Err((code, msg)) => error_response(id, code, &msg),
+    }]
+  }
+
+  fn notification(&mut self, method: &str, params: &Value) -> Vec<Value> {
+    let uri = params["textDocument"]["uri"]
+      .as_str()
+      .unwrap_or("")
+      .to_owned();
+
+    match method {
+      "exit" => self.exited = true,
+      "textDocument/didOpen" => {
+        let text = params["textDocument"]["text"].as_str().unwrap_or("");
+        return self.update(uri, text.to_owned());
+      }
+      "textDocument/didChange" => {
+        // Only full document sync is supported, so the last change holds all text
+        let changes = params["contentChanges"].as_array();
+        if let Some(text) = changes.and_then(|changes| changes.last()?["text"].as_str()) {
+          return self.update(uri, text.to_owned());
+        }
+      }
+      "textDocument/didClose" => {
+        self.documents.remove(&uri);
+        return vec![publish_diagnostics(&uri, Vec::new())];
+      }
+      // Unknown notifications are ignored, as the protocol requires
+      _ => (),
+    }
+
+    Vec::new()
+  }
+
+  fn update(&mut self, uri: String, text: String) -> Vec<Value> {
+    let document = Document::new(text);
+    let diagnostics = document
+      .diagnostics
+      .iter()
+      .map(|error| diagnostic(&uri, &document, error))
+      .collect();
+
+    self.documents.insert(uri.clone(), document);
+    vec![publish_diagnostics(&uri, diagnostics)]
+  }
+
+  /// Document named by the `textDocument` parameter
+  fn document(&self, params: &Value) -> Result<&Document, (i64, String)> {
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+    match self.documents.get(uri) {
+      Some(document) => Ok(document),
+      None => Err((INVALID_PARAMS, format!("unknown document {}", uri))),
+    }
+  }
+
+  /// Document and byte offset a `TextDocumentPositionParams` refers to
+  fn locate(&self, params: &Value) -> Result<(&Document, usize), (i64, String)> {
+    let document = self.document(params)?;
+
+    let position = &params["position"];
+    let (line, character) = match (position["line"].as_u64(), position["character"].as_u64()) {
+      (Some(line), Some(character)) => (line as usize, character as usize),
+      _ => return Err((INVALID_PARAMS, "missing position".to_owned())),
+    };
+
+    Ok((document, document.offset(line, character)))
+  }
+
+  fn hover(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (document, offset) = self.locate(params)?;
+
+    Ok(match document.reference_at(offset) {
+      Some((token, definition)) => json!({
+        "contents": {
+          "kind": "markdown",
+          "value": format!("```caravel\n{}: {}\n```", definition.name, type_name(definition)),
+        },
+        "range": range(document, document.token_span(token)),
+      }),
+      None => Value::Null,
+    })
+  }
+
+  fn definition(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (document, offset) = self.locate(params)?;
+
+    Ok(match document.reference_at(offset) {
+      Some((_, definition)) => json!({
+        "uri": params["textDocument"]["uri"],
+        "range": range(document, document.token_span(definition.token)),
+      }),
+      None => Value::Null,
+    })
+  }
+
+  fn completion(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (document, offset) = self.locate(params)?;
+
+    let items: Vec<Value> = document
+      .visible_at(offset)
+      .into_iter()
+      .map(|definition| {
+        json!({
+          "label": definition.name,
+          "kind": COMPLETION_KIND_VARIABLE,
+          "detail": type_name(definition),
+        })
+      })
+      .collect();
+    Ok(Value::Array(items))
+  }
+
+  fn document_symbols(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let document = self.document(params)?;
+
+    let symbols: Vec<Value> = document
+      .definitions
+      .iter()
+      .map(|definition| {
+        let range = range(document, document.token_span(definition.token));
+        json!({
+          "name": definition.name,
+          "detail": type_name(definition),
+          "kind": SYMBOL_KIND_VARIABLE,
+          "range": range,
+          "selectionRange": range,
+        })
+      })
+      .collect();
+    Ok(Value::Array(symbols))
+  }
+
+  fn rename(&self, params: &Value) -> Result<Value, (i64, String)> {
+    let (document, offset) = self.locate(params)?;
+    let new_name = match params["newName"].as_str() {
+      Some(new_name) => new_name,
+      None => return Err((INVALID_PARAMS, "missing newName".to_owned())),
+    };
+
+    let spans = match rename(&document.text, offset, new_name) {
+      Ok(Some(spans)) => spans,
+      Ok(None) => return Ok(Value::Null),
+      Err(error) => return Err((REQUEST_FAILED, error.msg().to_owned())),
+    };
+
+    let edits: Vec<Value> = spans
+      .iter()
+      .map(|span| json!({ "range": range(document, span), "newText": new_name }))
+      .collect();
+    let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+    Ok(json!({ "changes": { uri: edits } }))
+  }
+}
+
+/// Runs a server reading messages from `input` and writing
+/// to `output` until the client exits or closes `input`
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+  let mut server = Server::new();
+
+  while let Some(message) = read_message(&mut input)? {
+    for reply in server.handle(&message) {
+      write_message(&mut output, &reply)?;
+    }
+    if server.exited() {
+      break;
+    }
+  }
+
+  Ok(())
+}
+
+/// Reads one `Content-Length` framed message, or `None` at the end of input
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+  let mut len = None;
+
+  loop {
+    let mut header = String::new();
+    if input.read_line(&mut header)? == 0 {
+      return Ok(None);
+    }
+
+    let header = header.trim_end();
+    if header.is_empty() {
+      break;
+    }
+    if let Some(value) = header.strip_prefix("Content-Length:") {
+      len = value.trim().parse::<usize>().ok();
+    }
+  }
+
+  let len = len.ok_or_else(|| invalid_data("missing Content-Length header"))?;
+  let mut body = vec![0; len];
+  input.read_exact(&mut body)?;
+
+  serde_json::from_slice(&body)
+    .map(Some)
+    .map_err(|err| invalid_data(&err.to_string()))
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+  let body = message.to_string();
+  write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+  output.flush()
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn error_response(id: &Value, code: i64, msg: &str) -> Value {
+  json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": msg } })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<Value>) -> Value {
+  json!({
+    "jsonrpc": "2.0",
+    "method": "textDocument/publishDiagnostics",
+    "params": { "uri": uri, "diagnostics": diagnostics },
+  })
+}
+
+fn diagnostic(uri: &str, document: &Document, error: &Error) -> Value {
+  let related: Vec<Value> = error
+    .notes()
+    .iter()
+    .map(|(pos, msg)| {
+      json!({
+        "location": { "uri": uri, "range": range(document, pos) },
+        "message": msg,
+      })
+    })
+    .collect();
+  // The protocol has no place for suggestions
+  let msg = match error.help() {
+    Some(help) => format!("{}\nhelp: {}", error.msg(), help),
+    None => error.msg().to_owned(),
+  };
+
+  json!({
+    "range": range(document, error.pos()),
+    "severity": match error.severity() {
+      Severity::Error => 1,
+      Severity::Warning => 2,
+    },
+    "code": error.kind().code(),
+    "source": "caravel",
+    "message": msg,
+    "relatedInformation": related,
+  })
+}
+
+fn range(document: &Document, span: &SourceSpan) -> Value {
+  let (start_line, start_character) = document.position(span.start_pos.offset);
+  let (end_line, end_character) = document.position(span.end());
+  json!({
+    "start": { "line": start_line, "character": start_character },
+    "end": { "line": end_line, "character": end_character },
+  })
+}
+
+fn type_name(definition: &document::Definition) -> String {
+  match definition.typ {
+    Some(typ) => typ.to_string(),
+    None => "{unknown}".to_owned(),
+  }
+}
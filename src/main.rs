@@ -1,44 +1,391 @@
-#![feature(never_type)]
+use caravel::ansi::{ColorMode, Stream};
+use caravel::bytecode::{self, Program};
+use caravel::error::explain::explain;
+use caravel::repl::Session;
+use caravel::{compile, format_error, CompileOptions, Emit};
+use caravel::{formatter, lsp, rename};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::{env, fs, process};
 
-pub mod analyzer;
-pub mod ansi;
-pub mod backend;
-pub mod error;
-pub mod lexer;
-pub mod parser;
-pub mod position;
-pub mod symbol_table;
-pub mod types;
+const FILENAME: &str = "input/input.cv";
 
-use analyzer::Analyzer;
-use error::{print_error, Result};
-use lexer::lexer::Lexer;
-use parser::parser::Parser;
-use std::fs;
+fn main() {
+  let mut args: Vec<String> = env::args().skip(1).collect();
+  let color = match take_flag(&mut args, "color") {
+    Some(value) => ColorMode::parse(&value).unwrap_or_else(|| {
+      eprintln!("error: `--color` expects one of `auto`, `always` or `never`");
+      process::exit(2);
+    }),
+    None => ColorMode::Auto,
+  };
+  let emit = match take_flag(&mut args, "emit") {
+    Some(value) => Emit::parse(&value).unwrap_or_else(|| {
+      eprintln!("error: `--emit` expects one of `tokens`, `tokens-json`, `ast`, `ast-dot`, `ast-json`, `ir`, `wat`, `c`, `asm` or `bytecode`");
+      process::exit(2);
+    }),
+    None => Emit::Ast,
+  };
 
-const FILENAME: &'static str = "input/input.cv";
+  if let Some("--explain") = args.first().map(|arg| &arg[..]) {
+    let code = match args.get(1) {
+      Some(code) => code,
+      None => {
+        eprintln!("usage: caravel --explain <code>");
+        process::exit(2);
+      }
+    };
 
-fn compile(source: &str) -> Result<()> {
-  let lexer = Lexer::new(source);
-  let tokens = lexer.lex()?;
+    match explain(code) {
+      Some(text) => print!("{}", text),
+      None => {
+        eprintln!("error: no explanation for error code `{}`", code);
+        process::exit(1);
+      }
+    }
+    return;
+  }
 
-  let parser = Parser::new(&tokens);
-  let block = parser.parse()?;
+  if let Some("repl") = args.first().map(|arg| &arg[..]) {
+    repl(color);
+    return;
+  }
 
-  let mut analyzer = Analyzer::new(&tokens);
-  analyzer.analyze(&block)?;
+  if let Some("lsp") = args.first().map(|arg| &arg[..]) {
+    if let Err(err) = lsp::serve(io::stdin().lock(), io::stdout().lock()) {
+      eprintln!("error: {}", err);
+      process::exit(1);
+    }
+    return;
+  }
 
-  backend::print::print(&block);
+  if let Some("fmt") = args.first().map(|arg| &arg[..]) {
+    fmt(&args[1..], color);
+    return;
+  }
 
-  Ok(())
-}
+  if let Some("rename") = args.first().map(|arg| &arg[..]) {
+    rename(&args[1..], color);
+    return;
+  }
+
+  if let Some("build") = args.first().map(|arg| &arg[..]) {
+    build(&args[1..], color);
+    return;
+  }
+
+  if let Some("run") = args.first().map(|arg| &arg[..]) {
+    run(&args[1..], color);
+    return;
+  }
 
-fn main() {
   let source = fs::read_to_string(FILENAME).unwrap();
 
-  let result = compile(&source);
+  let options = CompileOptions {
+    emit,
+    color: color.enabled(Stream::Stdout),
+  };
+  let output = compile(&source, &options);
+
+  for diagnostic in &output.diagnostics {
+    eprint!(
+      "{}",
+      format_error(diagnostic, &source, color.enabled(Stream::Stderr))
+    );
+  }
+
+  if let Some(output) = &output.output {
+    print!("{}", output);
+  }
+
+  if !output.succeeded() {
+    process::exit(1);
+  }
+}
+
+/// Formats files in place, or with `--check` only reports the
+/// ones that aren't formatted and exits with status 1 if any
+fn fmt(args: &[String], color: ColorMode) {
+  let check = args.iter().any(|arg| arg == "--check");
+  let mut paths: Vec<&str> = args
+    .iter()
+    .filter(|arg| *arg != "--check")
+    .map(|arg| &arg[..])
+    .collect();
+  if paths.is_empty() {
+    paths.push(FILENAME);
+  }
+
+  let mut failed = false;
+  for path in paths {
+    let source = match fs::read_to_string(path) {
+      Ok(source) => source,
+      Err(err) => {
+        eprintln!("error: cannot read {}: {}", path, err);
+        failed = true;
+        continue;
+      }
+    };
+
+    let formatted = match formatter::format(&source) {
+      Ok(formatted) => formatted,
+      Err(error) => {
+        eprint!(
+          "{}",
+          format_error(&error, &source, color.enabled(Stream::Stderr))
+        );
+        failed = true;
+        continue;
+      }
+    };
+
+    if formatted == source {
+      continue;
+    }
+
+    if check {
+      println!("{} is not formatted", path);
+      failed = true;
+    } else if let Err(err) = fs::write(path, formatted) {
+      eprintln!("error: cannot write {}: {}", path, err);
+      failed = true;
+    }
+  }
+
+  if failed {
+    process::exit(1);
+  }
+}
+
+/// Renames the variable at `--at line:col` (both one-based) to `--to`
+/// in place, everywhere it is used
+fn rename(args: &[String], color: ColorMode) {
+  let usage = || -> ! {
+    eprintln!("usage: caravel rename <file> --at <line>:<col> --to <name>");
+    process::exit(2);
+  };
+  let flag = |name: &str| {
+    let index = args.iter().position(|arg| arg == name)?;
+    args.get(index + 1).map(|value| &value[..])
+  };
+
+  let (path, at, new_name) = match (args.first(), flag("--at"), flag("--to")) {
+    (Some(path), Some(at), Some(new_name)) if !path.starts_with("--") => (path, at, new_name),
+    _ => usage(),
+  };
+  let (line, column) = match at.split_once(':') {
+    Some((line, column)) => match (line.parse::<usize>(), column.parse::<usize>()) {
+      (Ok(line), Ok(column)) if line > 0 && column > 0 => (line - 1, column - 1),
+      _ => usage(),
+    },
+    None => usage(),
+  };
+
+  let source = match fs::read_to_string(path) {
+    Ok(source) => source,
+    Err(err) => {
+      eprintln!("error: cannot read {}: {}", path, err);
+      process::exit(1);
+    }
+  };
+
+  let offset = match rename::offset(&source, line, column) {
+    Some(offset) => offset,
+    None => {
+      eprintln!("error: {} has no position {}", path, at);
+      process::exit(1);
+    }
+  };
+
+  let spans = match rename::rename(&source, offset, new_name) {
+    Ok(Some(spans)) => spans,
+    Ok(None) => {
+      eprintln!("error: no variable at {}:{}", path, at);
+      process::exit(1);
+    }
+    Err(error) => {
+      eprint!(
+        "{}",
+        format_error(&error, &source, color.enabled(Stream::Stderr))
+      );
+      process::exit(1);
+    }
+  };
+
+  if let Err(err) = fs::write(path, rename::apply(&source, &spans, new_name)) {
+    eprintln!("error: cannot write {}: {}", path, err);
+    process::exit(1);
+  }
+}
+
+/// Compiles a file to bytecode, written next to it with
+/// a `.cvc` extension unless `-o` says otherwise
+fn build(args: &[String], color: ColorMode) {
+  let (path, out) = match args {
+    [] => (FILENAME, None),
+    [flag, out] if flag == "-o" => (FILENAME, Some(&out[..])),
+    [path] => (&path[..], None),
+    [path, flag, out] if flag == "-o" => (&path[..], Some(&out[..])),
+    _ => {
+      eprintln!("usage: caravel build [file] [-o <out.cvc>]");
+      process::exit(2);
+    }
+  };
+  let out = match out {
+    Some(out) => out.to_owned(),
+    None => Path::new(path).with_extension("cvc").display().to_string(),
+  };
+
+  let program = compile_bytecode(path, color);
+  let written = fs::File::create(&out)
+    .and_then(|file| bytecode::file::write(&program, &mut BufWriter::new(file)));
+  if let Err(err) = written {
+    eprintln!("error: cannot write {}: {}", out, err);
+    process::exit(1);
+  }
+}
+
+/// Runs a source file or a `.cvc` file from `build`
+/// on the virtual machine, and prints its value
+fn run(args: &[String], color: ColorMode) {
+  let path = match args {
+    [] => FILENAME,
+    [path] => &path[..],
+    _ => {
+      eprintln!("usage: caravel run [file]");
+      process::exit(2);
+    }
+  };
+
+  let program = if path.ends_with(".cvc") {
+    let read =
+      fs::File::open(path).and_then(|file| bytecode::file::read(&mut BufReader::new(file)));
+    match read {
+      Ok(program) => program,
+      Err(err) => {
+        eprintln!("error: cannot read {}: {}", path, err);
+        process::exit(1);
+      }
+    }
+  } else {
+    compile_bytecode(path, color)
+  };
+
+  match bytecode::vm::run(&program) {
+    Ok(Some(value)) => println!("{}", value),
+    Ok(None) => {}
+    Err(msg) => {
+      eprintln!("error: invalid bytecode in {}: {}", path, msg);
+      process::exit(1);
+    }
+  }
+}
+
+/// Compiles a source file, exiting if it has errors
+fn compile_bytecode(path: &str, color: ColorMode) -> Program {
+  let source = match fs::read_to_string(path) {
+    Ok(source) => source,
+    Err(err) => {
+      eprintln!("error: cannot read {}: {}", path, err);
+      process::exit(1);
+    }
+  };
+
+  let options = CompileOptions {
+    emit: Emit::Nothing,
+    ..CompileOptions::default()
+  };
+  let output = compile(&source, &options);
+  for diagnostic in &output.diagnostics {
+    eprint!(
+      "{}",
+      format_error(diagnostic, &source, color.enabled(Stream::Stderr))
+    );
+  }
+  match (output.succeeded(), &output.ast, &output.resolution) {
+    (true, Some(ast), Some(resolution)) => bytecode::compile::compile(ast, resolution),
+    _ => process::exit(1),
+  }
+}
+
+fn repl(color: ColorMode) {
+  let mut session = Session::new(color.enabled(Stream::Stdout));
+  let mut lines = io::stdin().lock().lines();
+
+  loop {
+    let mut input = String::new();
+
+    // Keep reading until every '{' has been closed
+    loop {
+      print!("{}", if input.is_empty() { "> " } else { ". " });
+      io::stdout().flush().unwrap();
+
+      match lines.next() {
+        Some(Ok(line)) => input += &line,
+        _ => return,
+      }
+
+      if Session::is_complete(&input) {
+        break;
+      }
+      input.push('\n');
+    }
+
+    if input.trim().is_empty() {
+      continue;
+    }
+
+    let reply = session.eval(&input);
+    for diagnostic in &reply.diagnostics {
+      eprint!(
+        "{}",
+        format_error(diagnostic, &input, color.enabled(Stream::Stderr))
+      );
+    }
+    if let Some(output) = &reply.output {
+      print!("{}", output);
+    }
+  }
+}
+
+/// Removes `--<name> <value>` or `--<name>=<value>` from `args`,
+/// returning the value, which is empty if it's missing
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+  let flag = format!("--{}", name);
+  let with_value = format!("{}=", flag);
+  let index = args
+    .iter()
+    .position(|arg| *arg == flag || arg.starts_with(&with_value))?;
+
+  let arg = args.remove(index);
+  Some(match arg.strip_prefix(&with_value) {
+    Some(value) => value.to_owned(),
+    None if arg == flag && index < args.len() => args.remove(index),
+    None => String::new(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::take_flag;
+
+  fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+  }
+
+  #[test]
+  fn takes_flags_with_separate_or_attached_values() {
+    let mut rest = args(&["--color", "never", "run", "--emit=c"]);
+    assert_eq!(take_flag(&mut rest, "color"), Some("never".into()));
+    assert_eq!(take_flag(&mut rest, "emit"), Some("c".into()));
+    assert_eq!(rest, ["run"]);
+    assert_eq!(take_flag(&mut rest, "emit"), None);
+  }
 
-  if let Err(error) = result {
-    print_error(&error, &source);
+  #[test]
+  fn leaves_flags_merely_starting_with_the_name() {
+    let mut rest = args(&["--colorful", "--color-mode=x", "--color"]);
+    assert_eq!(take_flag(&mut rest, "color"), Some(String::new()));
+    assert_eq!(rest, ["--colorful", "--color-mode=x"]);
   }
 }
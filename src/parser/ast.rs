@@ -1,120 +1,224 @@
 use crate::position::token_position::TokenSpan;
+use std::convert::TryFrom;
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+
+/// Refers to a node of type `T` in an `Ast`
+pub struct NodeId<T> {
+  index: u32,
+  kind: PhantomData<fn() -> T>,
+}
 
-pub trait Node {
-  fn pos(&self) -> TokenSpan;
+impl<T> NodeId<T> {
+  /// Position of the node in its `Ast`, unique among nodes of all types
+  pub fn index(self) -> usize {
+    self.index as usize
+  }
 }
 
-pub enum Expression {
-  Assignment(Assignment),
-  Block(Block),
-  Term(Term),
+// Derived impls would only apply to ids of nodes implementing the trait
+impl<T> Clone for NodeId<T> {
+  fn clone(&self) -> Self {
+    *self
+  }
 }
 
-impl Node for Expression {
-  fn pos(&self) -> TokenSpan {
-    match self {
-      Self::Assignment(assig) => assig.pos(),
-      Self::Block(block) => block.pos(),
-      Self::Term(term) => term.pos(),
-    }
+impl<T> Copy for NodeId<T> {}
+
+impl<T> PartialEq for NodeId<T> {
+  fn eq(&self, other: &Self) -> bool {
+    self.index == other.index
   }
 }
 
-pub struct Block {
-  pub expressions: Vec<Expression>,
-  pub start_index: usize,
+impl<T> Eq for NodeId<T> {}
+
+impl<T> Hash for NodeId<T> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.index.hash(state);
+  }
 }
 
-impl Node for Block {
-  fn pos(&self) -> TokenSpan {
-    if self.expressions.len() == 0 {
-      return TokenSpan {
-        start: self.start_index,
-        len: 2,
-      };
+impl<T> Debug for NodeId<T> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "NodeId({})", self.index)
+  }
+}
+
+/// Syntax tree of a program. Nodes live in a single arena and refer
+/// to their children by `NodeId`; indexing the tree with an id gives
+/// its node. Nodes replaced by later passes stay in the arena,
+/// unreachable from the root
+#[derive(Debug)]
+pub struct Ast {
+  nodes: Vec<(Node, TokenSpan)>,
+  root: NodeId<Block>,
+}
+
+impl Ast {
+  /// Empty tree, whose root is set once the parser has built it
+  pub(super) fn new() -> Self {
+    Self {
+      nodes: Vec::new(),
+      root: NodeId {
+        index: 0,
+        kind: PhantomData,
+      },
     }
+  }
 
-    let mut pos = self.expressions[0].pos();
+  pub(super) fn set_root(&mut self, root: NodeId<Block>) {
+    self.root = root;
+  }
+
+  /// The program's block, whose brackets are inserted by the lexer
+  pub fn root(&self) -> NodeId<Block> {
+    self.root
+  }
 
-    match self.expressions.iter().last() {
-      Some(last) => pos = pos + last.pos(),
-      None => (),
+  /// Adds a node spanning `span`
+  pub fn push<T: Kind>(&mut self, node: T, span: TokenSpan) -> NodeId<T> {
+    self.nodes.push((node.into_node(), span));
+    NodeId {
+      index: u32::try_from(self.nodes.len() - 1).unwrap(),
+      kind: PhantomData,
     }
+  }
 
-    pos
+  /// Tokens from the first to the last one of a node
+  pub fn span<T>(&self, id: NodeId<T>) -> TokenSpan {
+    self.nodes[id.index()].1
   }
-}
 
-pub enum Assignment {
-  Initialization(String, String, Option<Box<Expression>>, usize),
-  Reassignment(String, Box<Expression>),
-}
-
-impl Node for Assignment {
-  fn pos(&self) -> TokenSpan {
-    match self {
-      Self::Initialization(_, _, rhs, start) => TokenSpan::new(
-        *start,
-        5 + match rhs {
-          Some(rhs) => rhs.pos().len,
-          None => 0,
-        },
-      ),
-      Self::Reassignment(_, expr) => expr.pos() - 2,
+  /// Index of the token naming the assigned variable
+  pub fn ident_token(&self, assig: NodeId<Assignment>) -> usize {
+    let start = self.span(assig).start;
+    match self[assig] {
+      Assignment::Initialization(Mutability::Immutable, ..) => start + 1,
+      Assignment::Initialization(Mutability::Mutable, ..) => start + 2,
+      Assignment::Reassignment(..) => start,
     }
   }
 }
 
-pub enum TermOp {
-  Add,
-  Subtract,
+// Ids are only made by `push`, with the type of their node, so
+// indexing only fails with an id of another tree
+impl<T: Kind> Index<NodeId<T>> for Ast {
+  type Output = T;
+
+  fn index(&self, id: NodeId<T>) -> &T {
+    T::from_node(&self.nodes[id.index()].0).unwrap()
+  }
 }
 
-pub enum Term {
+impl<T: Kind> IndexMut<NodeId<T>> for Ast {
+  fn index_mut(&mut self, id: NodeId<T>) -> &mut T {
+    T::from_node_mut(&mut self.nodes[id.index()].0).unwrap()
+  }
+}
+
+/// A node of any type, as stored in an `Ast`
+#[derive(Debug)]
+pub enum Node {
+  Expression(Expression),
+  Block(Block),
+  Assignment(Assignment),
+  Term(Term),
   Factor(Factor),
-  Operation(Box<Term>, TermOp, Factor),
+  Leaf(Leaf),
 }
 
-impl Node for Term {
-  fn pos(&self) -> TokenSpan {
-    match self {
-      Self::Factor(factor) => factor.pos(),
-      Self::Operation(lhs, _, rhs) => lhs.pos() + rhs.pos(),
+/// Types of the nodes an `Ast` holds
+pub trait Kind: Sized {
+  fn into_node(self) -> Node;
+  fn from_node(node: &Node) -> Option<&Self>;
+  fn from_node_mut(node: &mut Node) -> Option<&mut Self>;
+}
+
+macro_rules! kinds {
+  ($($kind:ident),*) => {$(
+    impl Kind for $kind {
+      fn into_node(self) -> Node {
+        Node::$kind(self)
+      }
+
+      fn from_node(node: &Node) -> Option<&Self> {
+        match node {
+          Node::$kind(node) => Some(node),
+          _ => None,
+        }
+      }
+
+      fn from_node_mut(node: &mut Node) -> Option<&mut Self> {
+        match node {
+          Node::$kind(node) => Some(node),
+          _ => None,
+        }
+      }
     }
-  }
+  )*};
+}
+
+kinds!(Expression, Block, Assignment, Term, Factor, Leaf);
+
+#[derive(Copy, Clone, Debug)]
+pub enum Expression {
+  Assignment(NodeId<Assignment>),
+  Block(NodeId<Block>),
+  Term(NodeId<Term>),
+}
+
+/// Expressions between brackets, which its span includes
+#[derive(Debug)]
+pub struct Block {
+  pub expressions: Vec<NodeId<Expression>>,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Mutability {
+  Immutable,
+  /// Declared with `let mut`, can be reassigned
+  Mutable,
+}
+
+#[derive(Debug)]
+pub enum Assignment {
+  /// Mutability, name, type as written and value
+  Initialization(Mutability, String, String, Option<NodeId<Expression>>),
+  Reassignment(String, NodeId<Expression>),
 }
 
+#[derive(Copy, Clone, Debug)]
+pub enum TermOp {
+  Add,
+  Subtract,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum Term {
+  Factor(NodeId<Factor>),
+  Operation(NodeId<Term>, TermOp, NodeId<Factor>),
+}
+
+#[derive(Copy, Clone, Debug)]
 pub enum FactorOp {
   Multiply,
   Divide,
   Modulo,
 }
 
+#[derive(Copy, Clone, Debug)]
 pub enum Factor {
-  Leaf(Leaf),
-  Operation(Box<Factor>, FactorOp, Leaf),
-}
-
-impl Node for Factor {
-  fn pos(&self) -> TokenSpan {
-    match self {
-      Self::Leaf(leaf) => leaf.pos(),
-      Self::Operation(lhs, _, rhs) => lhs.pos() + rhs.pos(),
-    }
-  }
+  Leaf(NodeId<Leaf>),
+  Operation(NodeId<Factor>, FactorOp, NodeId<Leaf>),
 }
 
+#[derive(Debug)]
 pub enum Leaf {
-  Identifier(String, usize),
-  FloatLiteral(String, usize),
-  Term(Box<Term>),
-}
-
-impl Node for Leaf {
-  fn pos(&self) -> TokenSpan {
-    match self {
-      Self::Identifier(_, start) | Self::FloatLiteral(_, start) => TokenSpan::new(*start, 1),
-      Self::Term(term) => term.pos(),
-    }
-  }
+  Identifier(String),
+  FloatLiteral(String),
+  /// A parenthesized term, whose span includes the parentheses
+  Term(NodeId<Term>),
 }
@@ -2,6 +2,7 @@ use super::ast::*;
 use crate::error::{Error, ErrorKind, Result};
 use crate::lexer::token::{Token, TokenKind};
 use crate::position::source_position::DEFAULT_REAL_SPAN;
+use crate::position::token_position::TokenSpan;
 
 const EOF_TOKEN: Token = Token {
   kind: TokenKind::Eof,
@@ -11,27 +12,47 @@ const EOF_TOKEN: Token = Token {
 pub struct Parser<'a> {
   tokens: &'a Vec<Token>,
   index: usize,
+  ast: Ast,
 }
 
 impl<'a> Parser<'a> {
   pub fn new(tokens: &'a Vec<Token>) -> Self {
-    Self { tokens, index: 0 }
+    Self {
+      tokens,
+      index: 0,
+      ast: Ast::new(),
+    }
   }
 
-  pub fn parse(mut self) -> Result<Block> {
-    self.parse_block()
+  pub fn parse(mut self) -> Result<Ast> {
+    let program = self.parse_block()?;
+
+    // A stray '}' closes the program block early
+    if self.index < self.tokens.len() {
+      return Err(Error::new(
+        ErrorKind::UnexpectedToken,
+        "unmatched '}'",
+        self.tokens[self.index - 1].pos.clone(),
+      ));
+    }
+
+    self.ast.set_root(program);
+    Ok(self.ast)
   }
 
   // Helpers
+  /// Adds a node spanning the tokens from `start` to the last one eaten
+  fn push<T: Kind>(&mut self, node: T, start: usize) -> NodeId<T> {
+    let span = TokenSpan::new(start, self.index - start);
+    self.ast.push(node, span)
+  }
+
   fn get(&self) -> &Token {
-    self.tokens.get(self.index as usize).unwrap_or(&EOF_TOKEN)
+    self.tokens.get(self.index).unwrap_or(&EOF_TOKEN)
   }
 
   fn peek(&self) -> &Token {
-    self
-      .tokens
-      .get(self.index as usize + 1)
-      .unwrap_or(&EOF_TOKEN)
+    self.tokens.get(self.index + 1).unwrap_or(&EOF_TOKEN)
   }
 
   fn eat(&mut self) -> &Token {
@@ -39,9 +60,8 @@ impl<'a> Parser<'a> {
     self.get()
   }
 
-  #[allow(unused_variables)]
   fn eat_if_get(&mut self, kind: TokenKind, expected: &str) -> Result<&Token> {
-    if !matches!(self.get(), kind) {
+    if self.get().kind != kind {
       self.throw_unexpected_token_msg(&format!("expected {}", expected))?;
     };
     Ok(self.eat())
@@ -61,18 +81,16 @@ impl<'a> Parser<'a> {
 
   fn get_and_eat(&mut self) -> &Token {
     self.index += 1;
-    self
-      .tokens
-      .get(self.index as usize - 1)
-      .unwrap_or(&EOF_TOKEN)
+    self.tokens.get(self.index - 1).unwrap_or(&EOF_TOKEN)
   }
 
   // Parse functions
   // expression : assignment
   //            | block
   //            | term
-  fn parse_expression(&mut self) -> Result<Expression> {
-    Ok(match self.get().kind {
+  fn parse_expression(&mut self) -> Result<NodeId<Expression>> {
+    let start = self.index;
+    let expr = match self.get().kind {
       TokenKind::LBracket => Expression::Block(self.parse_block()?),
       TokenKind::Let => Expression::Assignment(self.parse_assignment()?),
       TokenKind::Identifier(_) => {
@@ -83,12 +101,13 @@ impl<'a> Parser<'a> {
         }
       }
       _ => Expression::Term(self.parse_term()?),
-    })
+    };
+    Ok(self.push(expr, start))
   }
 
   // block : '{' ((expression)? EOL)* (expression)? '}'
-  fn parse_block(&mut self) -> Result<Block> {
-    let start_index = self.index;
+  fn parse_block(&mut self) -> Result<NodeId<Block>> {
+    let start = self.index;
     self.eat(); // '{'
 
     let mut expressions = Vec::new();
@@ -113,19 +132,25 @@ impl<'a> Parser<'a> {
       };
     }
 
-    Ok(Block {
-      expressions,
-      start_index,
-    })
+    self.eat(); // '}'
+
+    Ok(self.push(Block { expressions }, start))
   }
 
-  // assignment : let identifier ':' identifier '=' expression    ; Initialization
-  //            | let identifier ':' identifier                   ; Initialization
-  //            | identifier '=' expression                       ; Reassignment
-  fn parse_assignment(&mut self) -> Result<Assignment> {
-    Ok(match self.get_and_eat().kind.clone() {
+  // assignment : let (mut)? identifier ':' identifier '=' expression    ; Initialization
+  //            | let (mut)? identifier ':' identifier                   ; Initialization
+  //            | identifier '=' expression                              ; Reassignment
+  fn parse_assignment(&mut self) -> Result<NodeId<Assignment>> {
+    let start = self.index;
+    let assig = match self.get_and_eat().kind.clone() {
       TokenKind::Let => {
-        let start_index = self.index - 1;
+        let mutability = match self.get().kind {
+          TokenKind::Mut => {
+            self.eat();
+            Mutability::Mutable
+          }
+          _ => Mutability::Immutable,
+        };
 
         let ident = match &self.get().kind {
           TokenKind::Identifier(ident) => ident.clone(),
@@ -143,32 +168,30 @@ impl<'a> Parser<'a> {
 
         self.eat();
 
-        if !matches!(self.get().kind, TokenKind::Assignment) {
-          return Ok(Assignment::Initialization(
-            ident,
-            type_ident,
-            None,
-            start_index,
-          ));
+        let value = match self.get().kind {
+          TokenKind::Assignment => {
+            self.eat();
+            Some(self.parse_expression()?)
+          }
+          _ => None,
         };
-
-        self.eat();
-
-        let value = self.parse_expression()?;
-        Assignment::Initialization(ident, type_ident, Some(Box::new(value)), start_index)
+        Assignment::Initialization(mutability, ident, type_ident, value)
       }
       TokenKind::Identifier(ident) => {
         self.eat_if_get(TokenKind::Assignment, "'='")?;
         let value = self.parse_expression()?;
-        Assignment::Reassignment(ident.to_owned(), Box::new(value))
+        Assignment::Reassignment(ident, value)
       }
       _ => self.throw_unexpected_token()?,
-    })
+    };
+    Ok(self.push(assig, start))
   }
 
   // term : factor (('+' | '-') factor)*
-  fn parse_term(&mut self) -> Result<Term> {
-    let mut term = Term::Factor(self.parse_factor()?);
+  fn parse_term(&mut self) -> Result<NodeId<Term>> {
+    let start = self.index;
+    let factor = self.parse_factor()?;
+    let mut term = self.push(Term::Factor(factor), start);
 
     loop {
       let op = match self.get().kind {
@@ -180,15 +203,17 @@ impl<'a> Parser<'a> {
       self.eat();
 
       let rhs = self.parse_factor()?;
-      term = Term::Operation(Box::new(term), op, rhs);
+      term = self.push(Term::Operation(term, op, rhs), start);
     }
 
     Ok(term)
   }
 
   // factor : leaf (('*' | '/' | '%') leaf)*
-  fn parse_factor(&mut self) -> Result<Factor> {
-    let mut factor = Factor::Leaf(self.parse_leaf()?);
+  fn parse_factor(&mut self) -> Result<NodeId<Factor>> {
+    let start = self.index;
+    let leaf = self.parse_leaf()?;
+    let mut factor = self.push(Factor::Leaf(leaf), start);
 
     loop {
       let op = match self.get().kind {
@@ -201,7 +226,7 @@ impl<'a> Parser<'a> {
       self.eat();
 
       let rhs = self.parse_leaf()?;
-      factor = Factor::Operation(Box::new(factor), op, rhs);
+      factor = self.push(Factor::Operation(factor, op, rhs), start);
     }
 
     Ok(factor)
@@ -209,16 +234,18 @@ impl<'a> Parser<'a> {
 
   // leaf : identifier
   //      | float_literal
-  fn parse_leaf(&mut self) -> Result<Leaf> {
-    Ok(match self.get_and_eat().kind.clone() {
-      TokenKind::Identifier(value) => Leaf::Identifier(value.to_owned(), self.index - 1),
-      TokenKind::FloatLiteral(value) => Leaf::FloatLiteral(value.to_owned(), self.index - 1),
+  fn parse_leaf(&mut self) -> Result<NodeId<Leaf>> {
+    let start = self.index;
+    let leaf = match self.get_and_eat().kind.clone() {
+      TokenKind::Identifier(value) => Leaf::Identifier(value),
+      TokenKind::FloatLiteral(value) => Leaf::FloatLiteral(value),
       TokenKind::LParen => {
         let term = self.parse_term()?;
         self.eat_if_get(TokenKind::RParen, "')'")?;
-        Leaf::Term(Box::new(term))
+        Leaf::Term(term)
       }
       _ => self.throw_unexpected_token()?,
-    })
+    };
+    Ok(self.push(leaf, start))
   }
 }
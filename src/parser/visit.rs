@@ -0,0 +1,178 @@
+//! Traversals of an `Ast`. Each `visit_*` method of `Visitor` and
+//! `VisitorMut` defaults to the matching `walk_*` function, which
+//! visits the node's children in evaluation order. A pass overrides
+//! the methods for the nodes it cares about, calling `walk_*` from
+//! them to keep descending
+
+use super::ast::*;
+
+pub trait Visitor {
+  fn visit_block(&mut self, ast: &Ast, block: NodeId<Block>) {
+    walk_block(self, ast, block);
+  }
+
+  fn visit_expression(&mut self, ast: &Ast, expr: NodeId<Expression>) {
+    walk_expression(self, ast, expr);
+  }
+
+  fn visit_assignment(&mut self, ast: &Ast, assig: NodeId<Assignment>) {
+    walk_assignment(self, ast, assig);
+  }
+
+  fn visit_term(&mut self, ast: &Ast, term: NodeId<Term>) {
+    walk_term(self, ast, term);
+  }
+
+  fn visit_factor(&mut self, ast: &Ast, factor: NodeId<Factor>) {
+    walk_factor(self, ast, factor);
+  }
+
+  fn visit_leaf(&mut self, ast: &Ast, leaf: NodeId<Leaf>) {
+    walk_leaf(self, ast, leaf);
+  }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast, block: NodeId<Block>) {
+  for &expr in &ast[block].expressions {
+    visitor.visit_expression(ast, expr);
+  }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast, expr: NodeId<Expression>) {
+  match ast[expr] {
+    Expression::Assignment(assig) => visitor.visit_assignment(ast, assig),
+    Expression::Block(block) => visitor.visit_block(ast, block),
+    Expression::Term(term) => visitor.visit_term(ast, term),
+  }
+}
+
+pub fn walk_assignment<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast, assig: NodeId<Assignment>) {
+  if let Some(val) = value(ast, assig) {
+    visitor.visit_expression(ast, val);
+  }
+}
+
+pub fn walk_term<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast, term: NodeId<Term>) {
+  match ast[term] {
+    Term::Factor(factor) => visitor.visit_factor(ast, factor),
+    Term::Operation(lhs, _, rhs) => {
+      visitor.visit_term(ast, lhs);
+      visitor.visit_factor(ast, rhs);
+    }
+  }
+}
+
+pub fn walk_factor<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast, factor: NodeId<Factor>) {
+  match ast[factor] {
+    Factor::Leaf(leaf) => visitor.visit_leaf(ast, leaf),
+    Factor::Operation(lhs, _, rhs) => {
+      visitor.visit_factor(ast, lhs);
+      visitor.visit_leaf(ast, rhs);
+    }
+  }
+}
+
+pub fn walk_leaf<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast, leaf: NodeId<Leaf>) {
+  if let Leaf::Term(term) = ast[leaf] {
+    visitor.visit_term(ast, term);
+  }
+}
+
+/// Like `Visitor`, for passes that rewrite the tree as they go.
+/// `walk_*_mut` descends into the children a node has when it's called
+pub trait VisitorMut {
+  fn visit_block(&mut self, ast: &mut Ast, block: NodeId<Block>) {
+    walk_block_mut(self, ast, block);
+  }
+
+  fn visit_expression(&mut self, ast: &mut Ast, expr: NodeId<Expression>) {
+    walk_expression_mut(self, ast, expr);
+  }
+
+  fn visit_assignment(&mut self, ast: &mut Ast, assig: NodeId<Assignment>) {
+    walk_assignment_mut(self, ast, assig);
+  }
+
+  fn visit_term(&mut self, ast: &mut Ast, term: NodeId<Term>) {
+    walk_term_mut(self, ast, term);
+  }
+
+  fn visit_factor(&mut self, ast: &mut Ast, factor: NodeId<Factor>) {
+    walk_factor_mut(self, ast, factor);
+  }
+
+  fn visit_leaf(&mut self, ast: &mut Ast, leaf: NodeId<Leaf>) {
+    walk_leaf_mut(self, ast, leaf);
+  }
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  ast: &mut Ast,
+  block: NodeId<Block>,
+) {
+  for i in 0..ast[block].expressions.len() {
+    let expr = ast[block].expressions[i];
+    visitor.visit_expression(ast, expr);
+  }
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  ast: &mut Ast,
+  expr: NodeId<Expression>,
+) {
+  match ast[expr] {
+    Expression::Assignment(assig) => visitor.visit_assignment(ast, assig),
+    Expression::Block(block) => visitor.visit_block(ast, block),
+    Expression::Term(term) => visitor.visit_term(ast, term),
+  }
+}
+
+pub fn walk_assignment_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  ast: &mut Ast,
+  assig: NodeId<Assignment>,
+) {
+  if let Some(val) = value(ast, assig) {
+    visitor.visit_expression(ast, val);
+  }
+}
+
+pub fn walk_term_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ast: &mut Ast, term: NodeId<Term>) {
+  match ast[term] {
+    Term::Factor(factor) => visitor.visit_factor(ast, factor),
+    Term::Operation(lhs, _, rhs) => {
+      visitor.visit_term(ast, lhs);
+      visitor.visit_factor(ast, rhs);
+    }
+  }
+}
+
+pub fn walk_factor_mut<V: VisitorMut + ?Sized>(
+  visitor: &mut V,
+  ast: &mut Ast,
+  factor: NodeId<Factor>,
+) {
+  match ast[factor] {
+    Factor::Leaf(leaf) => visitor.visit_leaf(ast, leaf),
+    Factor::Operation(lhs, _, rhs) => {
+      visitor.visit_factor(ast, lhs);
+      visitor.visit_leaf(ast, rhs);
+    }
+  }
+}
+
+pub fn walk_leaf_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ast: &mut Ast, leaf: NodeId<Leaf>) {
+  if let Leaf::Term(term) = ast[leaf] {
+    visitor.visit_term(ast, term);
+  }
+}
+
+/// Value assigned by an assignment, if any
+fn value(ast: &Ast, assig: NodeId<Assignment>) -> Option<NodeId<Expression>> {
+  match ast[assig] {
+    Assignment::Initialization(_, _, _, val) => val,
+    Assignment::Reassignment(_, val) => Some(val),
+  }
+}
@@ -1,12 +1,12 @@
 pub const DEFAULT_SOURCE_POSITION: SourcePosition = SourcePosition {
+  offset: 0,
   line: 0,
   column: 0,
-  index: 0,
 };
 
 pub const DEFAULT_REAL_SPAN: SourceSpan = SourceSpan {
   start_pos: DEFAULT_SOURCE_POSITION,
-  source_len: 0,
+  len: 0,
 };
 
 // Represents a single position in the input
@@ -14,8 +14,13 @@ pub const DEFAULT_REAL_SPAN: SourceSpan = SourceSpan {
 pub struct SourcePosition {
   // must be first field so that Ord/PartialOrd
   // consider it first in their comparison algorithm
-  pub index: usize,
+  /// Byte offset from the start of the input. Always
+  /// lies on a UTF-8 character boundary
+  pub offset: usize,
+  /// Zero-based line number
   pub line: usize,
+  /// Zero-based column, counted in characters
+  /// (not bytes) from the start of the line
   pub column: usize,
 }
 
@@ -24,15 +29,16 @@ impl SourcePosition {
     DEFAULT_SOURCE_POSITION
   }
 
-  pub fn advance_ln(&mut self) {
-    self.index += 1;
-    self.column = 0;
-    self.line += 1;
-  }
+  /// Moves past `c`, which must be the character at this position
+  pub fn advance(&mut self, c: char) {
+    self.offset += c.len_utf8();
 
-  pub fn advance_col(&mut self) {
-    self.index += 1;
-    self.column += 1;
+    if c == '\n' {
+      self.column = 0;
+      self.line += 1;
+    } else {
+      self.column += 1;
+    }
   }
 }
 
@@ -40,14 +46,17 @@ impl SourcePosition {
 #[derive(Clone, Debug)]
 pub struct SourceSpan {
   pub start_pos: SourcePosition,
-  pub source_len: usize,
+  /// Length of the span in bytes
+  pub len: usize,
 }
 
 impl SourceSpan {
-  pub fn new(start_pos: SourcePosition, source_len: usize) -> Self {
-    Self {
-      start_pos,
-      source_len,
-    }
+  pub fn new(start_pos: SourcePosition, len: usize) -> Self {
+    Self { start_pos, len }
+  }
+
+  /// Byte offset one past the end of the span
+  pub fn end(&self) -> usize {
+    self.start_pos.offset + self.len
   }
 }
@@ -1,10 +1,9 @@
 use crate::lexer::token::Token;
 use crate::position::source_position::SourceSpan;
-use std::ops::{Add, Sub};
 
 pub const DEFAULT_TOKEN_SPAN: TokenSpan = TokenSpan { start: 0, len: 0 };
 
-#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Debug)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy, Debug)]
 pub struct TokenSpan {
   pub start: usize,
   pub len: usize,
@@ -15,34 +14,21 @@ impl TokenSpan {
     Self { start, len }
   }
 
-  pub fn as_source_span(&self, all_tokens: &Vec<Token>) -> SourceSpan {
+  /// Index of the last token
+  pub fn end(&self) -> usize {
+    self.start + self.len - 1
+  }
+
+  pub fn as_source_span(&self, all_tokens: &[Token]) -> SourceSpan {
     let first_tok = &all_tokens[self.start];
     let last_tok = &all_tokens[self.start + self.len - 1];
 
     SourceSpan::new(
       first_tok.pos.start_pos.clone(),
       match self.len == 1 {
-        true => first_tok.pos.source_len,
-        false => {
-          last_tok.pos.start_pos.index + last_tok.pos.source_len - first_tok.pos.start_pos.index
-        }
+        true => first_tok.pos.len,
+        false => last_tok.pos.end() - first_tok.pos.start_pos.offset,
       },
     )
   }
 }
-
-impl Add for TokenSpan {
-  type Output = Self;
-  fn add(self, rhs: Self) -> Self {
-    let min = self.min(rhs.clone());
-    let max = self.max(rhs);
-    Self::new(min.start, max.start + max.len - min.start)
-  }
-}
-
-impl Sub<usize> for TokenSpan {
-  type Output = Self;
-  fn sub(self, rhs: usize) -> Self {
-    Self::new(self.start - rhs, self.len + rhs)
-  }
-}
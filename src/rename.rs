@@ -0,0 +1,129 @@
+use crate::driver::{compile, CompileOptions, CompileOutput, Emit};
+use crate::error::{Error, ErrorKind, Result, Severity};
+use crate::lexer::lexer::Lexer;
+use crate::lexer::token::{Token, TokenKind};
+use crate::position::source_position::SourceSpan;
+use crate::resolver::Resolution;
+
+/// Spans of every occurrence of the variable at byte `offset`, to be
+/// replaced by `new_name`. Fails if `source` doesn't compile, or if
+/// renaming would make any identifier refer to another declaration.
+/// `Ok(None)` if there is no variable at `offset`
+pub fn rename(source: &str, offset: usize, new_name: &str) -> Result<Option<Vec<SourceSpan>>> {
+  let (tokens, resolution) = checked(compile(source, &options()))?;
+
+  // A cursor right after an identifier is still on it
+  let token = tokens.iter().position(|token| {
+    matches!(token.kind, TokenKind::Identifier(_))
+      && token.pos.start_pos.offset <= offset
+      && offset <= token.pos.end()
+  });
+  let id = match token.and_then(|token| resolution.symbol_at(token)) {
+    Some(id) => id,
+    None => return Ok(None),
+  };
+  let declaration = resolution.declaration(id);
+
+  let new_name = match identifier(new_name) {
+    Some(new_name) => new_name,
+    None => {
+      return Err(Error::new(
+        ErrorKind::InvalidRename,
+        &format!(r#""{}" is not a valid identifier"#, new_name),
+        tokens[declaration.token].pos.clone(),
+      ))
+    }
+  };
+
+  let mut occurrences = vec![declaration.token];
+  occurrences.extend(resolution.uses_of(id));
+  let spans: Vec<SourceSpan> = occurrences
+    .iter()
+    .map(|token| tokens[*token].pos.clone())
+    .collect();
+
+  // Renaming only swaps identifiers, so the renamed program has the same
+  // tokens, and must resolve every use to the same declaration as before
+  let renamed = checked(compile(&apply(source, &spans, &new_name), &options()));
+  if !matches!(renamed, Ok((_, ref renamed)) if renamed.uses == resolution.uses) {
+    let conflict = conflicting_declaration(&resolution, &new_name);
+    return Err(Error::new(
+      ErrorKind::InvalidRename,
+      &format!(
+        r#"renaming "{}" to "{}" would conflict with this declaration"#,
+        declaration.name, new_name
+      ),
+      tokens[conflict.unwrap_or(declaration.token)].pos.clone(),
+    ));
+  }
+
+  Ok(Some(spans))
+}
+
+/// Replaces the `spans` of `source`, which must be in order, with `new_name`
+pub fn apply(source: &str, spans: &[SourceSpan], new_name: &str) -> String {
+  let mut out = String::new();
+  let mut copied = 0;
+  for span in spans {
+    out += &source[copied..span.start_pos.offset];
+    out += new_name;
+    copied = span.end();
+  }
+  out + &source[copied..]
+}
+
+/// Byte offset of the zero-based `line` and `column` (in characters)
+pub fn offset(source: &str, line: usize, column: usize) -> Option<usize> {
+  let line_start = match line {
+    0 => 0,
+    _ => source.match_indices('\n').nth(line - 1)?.0 + 1,
+  };
+  let line_text = source[line_start..].split('\n').next().unwrap_or("");
+
+  match line_text.char_indices().nth(column) {
+    Some((i, _)) => Some(line_start + i),
+    // One past the last character
+    None if line_text.chars().count() == column => Some(line_start + line_text.len()),
+    None => None,
+  }
+}
+
+fn options() -> CompileOptions {
+  CompileOptions {
+    emit: Emit::Nothing,
+    ..CompileOptions::default()
+  }
+}
+
+fn checked(output: CompileOutput) -> Result<(Vec<Token>, Resolution)> {
+  if let Some(error) = output
+    .diagnostics
+    .into_iter()
+    .find(|diagnostic| diagnostic.severity() == Severity::Error)
+  {
+    return Err(error);
+  }
+  // Both are set when compilation succeeds
+  Ok((output.tokens.unwrap(), output.resolution.unwrap()))
+}
+
+/// `name` as the lexer reads it, if it is a single identifier
+fn identifier(name: &str) -> Option<String> {
+  let tokens = Lexer::new(name).lex().ok()?;
+  match &tokens[..] {
+    [_, Token {
+      kind: TokenKind::Identifier(ident),
+      ..
+    }, _] => Some(ident.clone()),
+    _ => None,
+  }
+}
+
+/// Token of a declaration named `name`, as variables can't share a name
+fn conflicting_declaration(resolution: &Resolution, name: &str) -> Option<usize> {
+  resolution
+    .declarations
+    .iter()
+    .find(|declaration| declaration.name == name)
+    .map(|declaration| declaration.token)
+}
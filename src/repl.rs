@@ -0,0 +1,132 @@
+use crate::analyzer::{Analyzer, Symbol};
+use crate::backend;
+use crate::error::{Error, Result};
+use crate::interpreter::{Environment, Interpreter, Value};
+use crate::lexer::{confusables, lexer::Lexer, token::Token, token::TokenKind};
+use crate::parser::{ast::Ast, parser::Parser};
+use crate::symbol_table::SymbolTable;
+use std::mem;
+
+const HELP: &str = "\
+Enter an expression to evaluate it. Declarations persist between inputs.
+Commands:
+  :type <expr>  show the type of <expr> without evaluating it
+  :ast <expr>   show the typed syntax tree of <expr>
+  :reset        forget all declarations
+  :help         show this message
+";
+
+/// State of an interactive session. Each input is analyzed and
+/// evaluated in the context of all inputs that succeeded before it
+pub struct Session {
+  symbol_table: SymbolTable<Symbol>,
+  env: Environment,
+  color: bool,
+}
+
+/// Result of a single input. Diagnostic positions are relative to that input
+#[derive(Debug, Default)]
+pub struct Reply {
+  pub diagnostics: Vec<Error>,
+  pub output: Option<String>,
+}
+
+impl Session {
+  /// `color` controls escape codes in rendered syntax trees
+  pub fn new(color: bool) -> Self {
+    Self {
+      symbol_table: SymbolTable::new(None),
+      env: Environment::default(),
+      color,
+    }
+  }
+
+  /// Whether `input` can be evaluated, or more lines are
+  /// needed to close the `{` blocks it opens
+  pub fn is_complete(input: &str) -> bool {
+    let tokens = match Lexer::new(input).lex() {
+      Ok(tokens) => tokens,
+      // Let evaluation report the error
+      Err(_) => return true,
+    };
+
+    let depth = tokens.iter().fold(0, |depth, token| match token.kind {
+      TokenKind::LBracket => depth + 1,
+      TokenKind::RBracket => depth - 1,
+      _ => depth,
+    });
+    depth <= 0
+  }
+
+  pub fn eval(&mut self, input: &str) -> Reply {
+    let mut reply = Reply::default();
+    if let Err(error) = self.eval_input(input, &mut reply) {
+      reply.diagnostics.push(error);
+    }
+    reply
+  }
+
+  fn eval_input(&mut self, input: &str, reply: &mut Reply) -> Result<()> {
+    let command = match input.trim_start().strip_prefix(':') {
+      Some(command) => command,
+      None => return self.eval_program(input, reply),
+    };
+
+    let name = command.split_whitespace().next().unwrap_or("");
+    // Blank out the command so that diagnostics for the
+    // expression line up with the original input
+    let prefix_len = input.find(name).unwrap_or(0) + name.len();
+    let expr = " ".repeat(prefix_len) + &input[prefix_len..];
+
+    match name {
+      "type" => {
+        let (tokens, ast) = parse(&expr, reply)?;
+        // Analyze against a copy so that declarations don't persist
+        let mut analyzer = Analyzer::with_symbol_table(&tokens, &ast, self.symbol_table.clone());
+        reply.output = Some(format!("{}\n", analyzer.analyze()?));
+      }
+      "ast" => {
+        let (tokens, ast) = parse(&expr, reply)?;
+        // Types are shown only if the expression is well typed
+        let mut analyzer = Analyzer::with_symbol_table(&tokens, &ast, self.symbol_table.clone());
+        let types = analyzer.analyze().ok().map(|_| analyzer.into_types());
+        reply.output = Some(backend::print::print(&ast, types.as_ref(), self.color));
+      }
+      "reset" => {
+        *self = Self::new(self.color);
+        reply.output = Some("session reset\n".to_owned());
+      }
+      "help" => reply.output = Some(HELP.to_owned()),
+      _ => reply.output = Some(format!("unknown command `:{}`, see `:help`\n", name)),
+    }
+
+    Ok(())
+  }
+
+  fn eval_program(&mut self, input: &str, reply: &mut Reply) -> Result<()> {
+    let (tokens, ast) = parse(input, reply)?;
+
+    // Only keep the declarations of inputs that analyze successfully
+    let mut analyzer = Analyzer::with_symbol_table(&tokens, &ast, self.symbol_table.clone());
+    let typ = analyzer.analyze()?;
+    self.symbol_table = analyzer.into_symbol_table();
+
+    let mut interpreter = Interpreter::with_environment(&tokens, &ast, mem::take(&mut self.env));
+    let result = interpreter.run();
+    self.env = interpreter.into_environment();
+
+    match result? {
+      Value::Void => (),
+      value => reply.output = Some(format!("{} : {}\n", value, typ)),
+    }
+
+    Ok(())
+  }
+}
+
+fn parse(source: &str, reply: &mut Reply) -> Result<(Vec<Token>, Ast)> {
+  let tokens = Lexer::new(source).lex()?;
+  reply.diagnostics.extend(confusables::check(&tokens));
+  let ast = Parser::new(&tokens).parse()?;
+  Ok((tokens, ast))
+}
@@ -0,0 +1,109 @@
+use crate::parser::ast::*;
+use crate::parser::visit::*;
+use crate::symbol_table::SymbolTable;
+use std::collections::HashMap;
+
+/// Identifies one declaration, as an index into `Resolution::declarations`
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SymbolId(pub usize);
+
+#[derive(Debug)]
+pub struct Declaration {
+  pub name: String,
+  pub type_ident: String,
+  pub mutability: Mutability,
+  /// Index of the declared identifier's token
+  pub token: usize,
+}
+
+/// Declarations of a program and the uses referring to them
+#[derive(Debug, Default)]
+pub struct Resolution {
+  pub declarations: Vec<Declaration>,
+  /// Token index of every identifier use -> the declaration it refers to.
+  /// Uses of undeclared variables are left out
+  pub uses: HashMap<usize, SymbolId>,
+  /// Token index of every declared identifier -> its declaration
+  pub declared: HashMap<usize, SymbolId>,
+}
+
+impl Resolution {
+  pub fn declaration(&self, id: SymbolId) -> &Declaration {
+    &self.declarations[id.0]
+  }
+
+  /// Symbol declared or used by the identifier at token index `token`
+  pub fn symbol_at(&self, token: usize) -> Option<SymbolId> {
+    self
+      .uses
+      .get(&token)
+      .or_else(|| self.declared.get(&token))
+      .copied()
+  }
+
+  /// Token indices of the uses of `id`, in source order
+  pub fn uses_of(&self, id: SymbolId) -> Vec<usize> {
+    let mut tokens: Vec<usize> = self
+      .uses
+      .iter()
+      .filter(|(_, symbol)| **symbol == id)
+      .map(|(token, _)| *token)
+      .collect();
+    tokens.sort_unstable();
+    tokens
+  }
+}
+
+/// Links every identifier in the program to its declaration,
+/// following the same scoping rules as the analyzer
+pub fn resolve(ast: &Ast) -> Resolution {
+  let mut resolver = Resolver {
+    symbol_table: SymbolTable::new(None),
+    resolution: Resolution::default(),
+  };
+  resolver.visit_block(ast, ast.root());
+  resolver.resolution
+}
+
+struct Resolver {
+  symbol_table: SymbolTable<SymbolId>,
+  resolution: Resolution,
+}
+
+impl Visitor for Resolver {
+  fn visit_assignment(&mut self, ast: &Ast, assig: NodeId<Assignment>) {
+    // The value is resolved first: it can't refer to the variable it initializes
+    walk_assignment(self, ast, assig);
+
+    match &ast[assig] {
+      Assignment::Initialization(mutability, ident, type_ident, _) => {
+        let id = SymbolId(self.resolution.declarations.len());
+        let token = ast.ident_token(assig);
+        self.resolution.declared.insert(token, id);
+        self.resolution.declarations.push(Declaration {
+          name: ident.clone(),
+          type_ident: type_ident.clone(),
+          mutability: *mutability,
+          token,
+        });
+        self.symbol_table.set(ident, id);
+      }
+      Assignment::Reassignment(ident, _) => self.reference(ident, ast.ident_token(assig)),
+    }
+  }
+
+  fn visit_leaf(&mut self, ast: &Ast, leaf: NodeId<Leaf>) {
+    match &ast[leaf] {
+      Leaf::Identifier(ident) => self.reference(ident, ast.span(leaf).start),
+      _ => walk_leaf(self, ast, leaf),
+    }
+  }
+}
+
+impl Resolver {
+  fn reference(&mut self, ident: &str, token: usize) {
+    if let Some(id) = self.symbol_table.get(ident) {
+      self.resolution.uses.insert(token, id);
+    }
+  }
+}
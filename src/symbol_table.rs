@@ -1,12 +1,14 @@
-use crate::types::Type;
 use std::collections::HashMap;
 
-pub struct SymbolTable {
+/// Maps the identifiers declared in a scope to `T`, falling
+/// back to the enclosing scopes for identifiers not declared in it
+#[derive(Clone)]
+pub struct SymbolTable<T> {
   parent: Option<Box<Self>>,
-  symbols: HashMap<String, Type>,
+  symbols: HashMap<String, T>,
 }
 
-impl SymbolTable {
+impl<T: Copy> SymbolTable<T> {
   pub fn new(parent: Option<Box<Self>>) -> Self {
     SymbolTable {
       parent,
@@ -14,13 +16,13 @@ impl SymbolTable {
     }
   }
 
-  pub fn set(&mut self, identifier: &str, sym_type: Type) {
-    self.symbols.insert(identifier.to_owned(), sym_type);
+  pub fn set(&mut self, identifier: &str, symbol: T) {
+    self.symbols.insert(identifier.to_owned(), symbol);
   }
 
-  pub fn get(&self, identifier: &str) -> Option<Type> {
-    if let Some(sym_type) = self.symbols.get(identifier) {
-      return Some(sym_type.clone());
+  pub fn get(&self, identifier: &str) -> Option<T> {
+    if let Some(symbol) = self.symbols.get(identifier) {
+      return Some(*symbol);
     };
 
     match &self.parent {
@@ -29,10 +31,27 @@ impl SymbolTable {
     }
   }
 
+  /// Symbol of `identifier` in the innermost scope declaring it
+  pub fn get_mut(&mut self, identifier: &str) -> Option<&mut T> {
+    match self.symbols.get_mut(identifier) {
+      Some(symbol) => Some(symbol),
+      None => match &mut self.parent {
+        Some(parent) => parent.get_mut(identifier),
+        None => None,
+      },
+    }
+  }
+
   pub fn has(&self, identifier: &str) -> bool {
-    match self.get(identifier) {
-      Some(_) => true,
-      None => false,
+    self.get(identifier).is_some()
+  }
+
+  /// Applies `f` to the symbols of every scope
+  pub fn for_each_mut(&mut self, mut f: impl FnMut(&mut T)) {
+    let mut scope = Some(self);
+    while let Some(table) = scope {
+      table.symbols.values_mut().for_each(&mut f);
+      scope = table.parent.as_deref_mut();
     }
   }
 }
@@ -1,4 +1,5 @@
 use std::convert::TryFrom;
+use std::fmt::{self, Display};
 
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum Type {
@@ -17,3 +18,12 @@ impl TryFrom<String> for Type {
     }
   }
 }
+
+impl Display for Type {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Type::Float => write!(f, "float"),
+      Type::Void => write!(f, "void"),
+    }
+  }
+}
@@ -0,0 +1,91 @@
+use crate::error::{Error, ErrorKind};
+use crate::lexer::token::Token;
+use crate::parser::ast::*;
+use crate::parser::visit::*;
+use crate::position::token_position::TokenSpan;
+use crate::resolver::{Resolution, SymbolId};
+use std::collections::{HashMap, HashSet};
+
+/// Warns about variables that are never read, and about values
+/// assigned to a variable that are overwritten before being read.
+/// Variables whose name starts with `_` are exempt
+pub fn check(ast: &Ast, tokens: &[Token], resolution: &Resolution) -> Vec<Error> {
+  let mut checker = Checker {
+    resolution,
+    read: HashSet::new(),
+    unread_write: HashMap::new(),
+    overwritten: Vec::new(),
+  };
+  checker.visit_block(ast, ast.root());
+
+  let exempt = |id: SymbolId| resolution.declaration(id).name.starts_with('_');
+  let mut warnings = Vec::new();
+
+  for (i, declaration) in resolution.declarations.iter().enumerate() {
+    let id = SymbolId(i);
+    if !checker.read.contains(&id) && !exempt(id) {
+      warnings.push(Error::warning(
+        ErrorKind::UnusedVariable,
+        &format!(r#"variable "{}" is never read"#, declaration.name),
+        tokens[declaration.token].pos.clone(),
+      ));
+    }
+  }
+
+  // Every write to a variable that's never read is dead, which
+  // the warning above already says
+  for (id, span) in checker.overwritten {
+    if checker.read.contains(&id) && !exempt(id) {
+      warnings.push(Error::warning(
+        ErrorKind::UnusedAssignment,
+        &format!(
+          r#"value assigned to "{}" is overwritten before being read"#,
+          resolution.declaration(id).name
+        ),
+        span.as_source_span(tokens),
+      ));
+    }
+  }
+
+  warnings
+}
+
+/// Walks the program in evaluation order, following reads and writes
+struct Checker<'a> {
+  resolution: &'a Resolution,
+  read: HashSet<SymbolId>,
+  /// Span of the last assignment to each variable, until it's read
+  unread_write: HashMap<SymbolId, TokenSpan>,
+  overwritten: Vec<(SymbolId, TokenSpan)>,
+}
+
+impl<'a> Visitor for Checker<'a> {
+  fn visit_assignment(&mut self, ast: &Ast, assig: NodeId<Assignment>) {
+    // A declaration without a value writes nothing
+    if let Assignment::Initialization(_, _, _, None) = ast[assig] {
+      return;
+    }
+    walk_assignment(self, ast, assig);
+
+    // Declarations always resolve, uses only if the variable is declared
+    let id = match self.resolution.symbol_at(ast.ident_token(assig)) {
+      Some(id) => id,
+      None => return,
+    };
+    if let Some(previous) = self.unread_write.insert(id, ast.span(assig)) {
+      self.overwritten.push((id, previous));
+    }
+  }
+
+  fn visit_leaf(&mut self, ast: &Ast, leaf: NodeId<Leaf>) {
+    match ast[leaf] {
+      Leaf::Identifier(_) => {
+        if let Some(id) = self.resolution.uses.get(&ast.span(leaf).start) {
+          self.read.insert(*id);
+          self.unread_write.remove(id);
+        }
+      }
+      _ => walk_leaf(self, ast, leaf),
+    }
+  }
+}
@@ -0,0 +1,56 @@
+use caravel::ast::*;
+use caravel::position::token_position::TokenSpan;
+use caravel::{Lexer, Parser};
+
+fn parse(source: &str) -> Ast {
+  Parser::new(&Lexer::new(source).lex().unwrap())
+    .parse()
+    .unwrap()
+}
+
+#[test]
+fn spans_include_brackets_and_parentheses() {
+  // { let a : float = ( 1 + 2 ) * 3 EOL { } }
+  let ast = parse("let a: float = (1 + 2) * 3\n{}");
+  let program = &ast[ast.root()];
+  assert_eq!(ast.span(ast.root()), TokenSpan::new(0, 17));
+
+  let assig = match ast[program.expressions[0]] {
+    Expression::Assignment(assig) => assig,
+    expr => panic!("expected an assignment, found {:?}", expr),
+  };
+  assert_eq!(ast.span(assig), TokenSpan::new(1, 12));
+  assert_eq!(ast.ident_token(assig), 2);
+
+  let value = match ast[assig] {
+    Assignment::Initialization(_, _, _, Some(value)) => value,
+    ref assig => panic!("expected an initializer, found {:?}", assig),
+  };
+  let term = match ast[value] {
+    Expression::Term(term) => term,
+    expr => panic!("expected a term, found {:?}", expr),
+  };
+  let (lhs, op) = match ast[term] {
+    Term::Factor(factor) => match ast[factor] {
+      Factor::Operation(lhs, op, _) => (lhs, op),
+      factor => panic!("expected an operation, found {:?}", factor),
+    },
+    term => panic!("expected a factor, found {:?}", term),
+  };
+  assert!(matches!(op, FactorOp::Multiply));
+  assert_eq!(ast.span(lhs), TokenSpan::new(6, 5));
+
+  assert_eq!(ast.span(program.expressions[1]), TokenSpan::new(14, 2));
+}
+
+#[test]
+fn ids_are_unique_across_node_types() {
+  let ast = parse("a = b");
+  let expr = ast[ast.root()].expressions[0];
+  let assig = match ast[expr] {
+    Expression::Assignment(assig) => assig,
+    expr => panic!("expected an assignment, found {:?}", expr),
+  };
+  assert_ne!(expr.index(), assig.index());
+  assert_ne!(expr.index(), ast.root().index());
+}
@@ -0,0 +1,119 @@
+use caravel::{compile, CompileOptions, Emit};
+use serde_json::{json, Value};
+
+const SOURCE: &str = "\
+let mut x: float = 1 + 1
+{
+  let y: float = x * (3 - x)
+  x = y
+}
+let v: void
+x
+";
+
+fn emit(source: &str, emit: Emit) -> String {
+  let options = CompileOptions {
+    emit,
+    ..CompileOptions::default()
+  };
+  let output = compile(source, &options);
+  assert!(output.succeeded(), "{:?}", output.diagnostics);
+  output.output.unwrap()
+}
+
+#[test]
+fn draws_graphviz_graphs() {
+  let dot = emit(SOURCE, Emit::AstDot);
+  assert!(dot.starts_with("digraph ast {\n"));
+  assert!(dot.ends_with("}\n"));
+  assert!(dot.contains("  n0 [label=\"Block\", tooltip=\"1:1, tokens 0..40\"];\n"));
+  assert!(dot.contains("[label=\"mut x\", tooltip=\"1:9, tokens 3..4\", shape=plaintext];\n"));
+  assert!(dot.contains("[label=\"Reassignment\", tooltip=\"4:3, tokens 26..29\"];\n"));
+
+  // Every node but the root has exactly one parent
+  let nodes = dot.matches("[label=").count();
+  let edges = dot.matches(" -> ").count();
+  assert_eq!(edges, nodes - 1);
+}
+
+#[test]
+fn exports_json() {
+  let document: Value = serde_json::from_str(&emit(SOURCE, Emit::AstJson)).unwrap();
+  assert_eq!(document["version"], 2);
+
+  let program = &document["program"];
+  assert_eq!(program["kind"], "Block");
+  assert_eq!(program["tokens"], json!({ "start": 0, "len": 40 }));
+  assert_eq!(program["type"], "float");
+
+  let expressions = program["expressions"].as_array().unwrap();
+  assert_eq!(expressions.len(), 4);
+  assert_eq!(
+    expressions[0],
+    json!({
+      "kind": "Initialization",
+      "tokens": { "start": 1, "len": 9 },
+      "name": "x",
+      "mutable": true,
+      "annotation": "float",
+      "type": "float",
+      "value": {
+        "kind": "FloatLiteral",
+        "tokens": { "start": 7, "len": 3 },
+        "type": "float",
+        "value": "2",
+      },
+    })
+  );
+  assert_eq!(
+    expressions[1]["expressions"][0]["value"],
+    json!({
+      "kind": "Binary",
+      "tokens": { "start": 18, "len": 7 },
+      "type": "float",
+      "op": "Multiply",
+      "lhs": {
+        "kind": "Identifier",
+        "tokens": { "start": 18, "len": 1 },
+        "type": "float",
+        "name": "x",
+      },
+      "rhs": {
+        "kind": "Binary",
+        "tokens": { "start": 21, "len": 3 },
+        "type": "float",
+        "op": "Subtract",
+        "lhs": {
+          "kind": "FloatLiteral",
+          "tokens": { "start": 21, "len": 1 },
+          "type": "float",
+          "value": "3",
+        },
+        "rhs": {
+          "kind": "Identifier",
+          "tokens": { "start": 23, "len": 1 },
+          "type": "float",
+          "name": "x",
+        },
+      },
+    })
+  );
+  assert_eq!(expressions[1]["type"], "float");
+  assert_eq!(expressions[2]["annotation"], "void");
+  assert_eq!(expressions[2]["type"], "void");
+  assert_eq!(expressions[2]["value"], Value::Null);
+}
+
+#[test]
+fn skips_programs_that_fail_to_compile() {
+  let options = CompileOptions {
+    emit: Emit::AstDot,
+    ..CompileOptions::default()
+  };
+  let output = compile("let é: float = \"", &options);
+  assert!(!output.succeeded());
+  assert!(output.output.is_none());
+
+  let dot = emit("let é: float = 2\né", Emit::AstDot);
+  assert!(dot.contains("[label=\"é\""));
+}
@@ -0,0 +1,160 @@
+use caravel::bytecode::{self, file, vm, Function, Op, Program};
+use caravel::{compile, CompileOptions, Emit};
+
+const SOURCE: &str = "\
+let mut x: float = 1 + 1
+let v: void = {}
+{
+  let y: float = x * 3
+  x = y - x
+}
+v
+x % 3 + 0.5
+";
+
+fn compile_bytecode(source: &str) -> Program {
+  let output = compile(source, &CompileOptions::default());
+  bytecode::compile::compile(&output.ast.unwrap(), &output.resolution.unwrap())
+}
+
+#[test]
+fn disassembles_the_compiled_program() {
+  let options = CompileOptions {
+    emit: Emit::Bytecode,
+    ..CompileOptions::default()
+  };
+  assert_eq!(
+    compile(SOURCE, &options).output.unwrap(),
+    "\
+constants:
+     0  2
+     1  3
+     2  0.5
+
+fn main (arity 0, 3 locals, returns):
+  0000  const  0    ; 2
+  0001  store  0    ; x
+  0002  load   0    ; x
+  0003  const  1    ; 3
+  0004  mul
+  0005  store  2    ; y
+  0006  load   2    ; y
+  0007  load   0    ; x
+  0008  sub
+  0009  store  0    ; x
+  0010  load   0    ; x
+  0011  const  1    ; 3
+  0012  rem
+  0013  const  2    ; 0.5
+  0014  add
+  0015  ret
+"
+  );
+}
+
+#[test]
+fn runs_programs() {
+  assert_eq!(vm::run(&compile_bytecode(SOURCE)), Ok(Some(1.5)));
+  assert_eq!(
+    vm::run(&compile_bytecode("let mut a: float\na = 2\na = a * a\na")),
+    Ok(Some(4.0))
+  );
+  assert_eq!(vm::run(&compile_bytecode("let v: void = {}\nv")), Ok(None));
+  assert_eq!(vm::run(&compile_bytecode("")), Ok(None));
+}
+
+#[test]
+fn runs_calls_and_jumps() {
+  // main() = twice(3, 0) with twice(n, acc) adding n to acc in a loop
+  // that runs two times
+  let program = Program {
+    constants: vec![3.0, 0.0, 2.0, 1.0],
+    functions: vec![
+      Function {
+        name: "main".to_owned(),
+        arity: 0,
+        locals: vec![],
+        returns_value: true,
+        code: vec![Op::Const(0), Op::Const(1), Op::Call(1), Op::Return],
+      },
+      Function {
+        name: "twice".to_owned(),
+        arity: 2,
+        locals: vec!["n".to_owned(), "acc".to_owned(), "i".to_owned()],
+        returns_value: true,
+        code: vec![
+          Op::Const(2),
+          Op::Store(2),
+          Op::Load(2),
+          Op::JumpIfZero(13),
+          Op::Load(1),
+          Op::Load(0),
+          Op::Add,
+          Op::Store(1),
+          Op::Load(2),
+          Op::Const(3),
+          Op::Subtract,
+          Op::Store(2),
+          Op::Jump(2),
+          Op::Load(1),
+          Op::Return,
+        ],
+      },
+    ],
+  };
+  assert_eq!(vm::run(&program), Ok(Some(6.0)));
+}
+
+#[test]
+fn rejects_broken_bytecode() {
+  let mut program = compile_bytecode("1 + 2");
+  program.functions[0].code.insert(0, Op::Add);
+  assert_eq!(vm::run(&program), Err("stack underflow".to_owned()));
+
+  program.functions[0].code = vec![Op::Load(0), Op::Return];
+  assert_eq!(vm::run(&program), Err("missing local slot 0".to_owned()));
+
+  program.functions[0].code = vec![Op::Jump(7)];
+  assert_eq!(
+    vm::run(&program),
+    Err("fn main ends without returning".to_owned())
+  );
+}
+
+#[test]
+fn round_trips_through_files() {
+  let program = compile_bytecode(SOURCE);
+  let mut bytes = Vec::new();
+  file::write(&program, &mut bytes).unwrap();
+  assert!(bytes.starts_with(file::MAGIC));
+  assert_eq!(file::read(&mut &bytes[..]).unwrap(), program);
+
+  // Truncated files
+  for len in 0..bytes.len() {
+    assert!(file::read(&mut &bytes[..len]).is_err());
+  }
+}
+
+#[test]
+fn rejects_other_versions() {
+  let mut bytes = Vec::new();
+  file::write(&compile_bytecode("1"), &mut bytes).unwrap();
+  bytes[4..6].copy_from_slice(&(file::VERSION + 1).to_le_bytes());
+
+  let err = file::read(&mut &bytes[..]).unwrap_err();
+  assert_eq!(
+    err.to_string(),
+    format!(
+      "unsupported bytecode version {} (expected {})",
+      file::VERSION + 1,
+      file::VERSION
+    )
+  );
+  assert!(file::read(&mut &b"#!/bin/sh\n"[..]).is_err());
+}
+
+#[test]
+fn shares_constants_between_equal_literals() {
+  let program = compile_bytecode("let mut a: float = 1\na = a * 2 + 1.0\na / 2.00");
+  assert_eq!(program.constants, [1.0, 2.0]);
+}
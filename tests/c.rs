@@ -0,0 +1,64 @@
+mod common;
+
+use caravel::Emit;
+use common::Native;
+
+const C: Native = Native {
+  emit: Emit::C,
+  extension: "c",
+  flags: &["-std=c99", "-pedantic-errors"],
+  libs: &["-lm"],
+};
+
+#[test]
+fn emits_flattened_c() {
+  let source = "\
+let mut x: float = 1 + 1
+let v: void = {}
+{
+  let z: float = 3
+}
+{
+  let y: float = 4
+}
+let _é: float = {
+  let w: void = v
+  x % 0.5
+}
+";
+  assert_eq!(
+    C.emit(source),
+    "\
+#include <math.h>
+#include <stdio.h>
+
+int main(void) {
+  double x_0;
+  double z_2;
+  double y_3;
+  double var5;
+  x_0 = 2.0;
+  z_2 = 3.0;
+  y_3 = 4.0;
+  var5 = fmod(x_0, 0.5);
+  printf(\"%.17g\\n\", var5);
+  return 0;
+}
+"
+  );
+}
+
+#[test]
+fn agrees_with_the_vm() {
+  C.assert_agrees_with_the_vm(
+    "agrees_with_the_vm",
+    &[
+      "let mut x: float = 1 + 1\n{\n  let y: float = x * 3\n  x = y - x\n}\nx % 3 + 0.5",
+      "let mut a: float = 1\nlet b: float = {\n  let c: float = 10\n  c / 3\n}\na = a - b\na * (a + b)",
+      "let mut x: float = 0 - 7.25\nlet mut y: float = 2\nx % y",
+      "let mut x: float = 1\nlet mut y: float = 0\nx / y",
+      "let mut x: float = 0\nx / x",
+      "let v: void = {}\nv",
+    ],
+  );
+}
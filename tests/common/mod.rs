@@ -0,0 +1,107 @@
+//! Helpers for the suites that build emitted code into native
+//! executables with the system C compiler, `cc`
+
+use caravel::bytecode::{self, vm};
+use caravel::{compile, CompileOptions, Emit};
+use std::io::{self, Write};
+use std::process::Command;
+use std::{env, fs};
+
+/// How to build the output of a backend with `cc`
+pub struct Native {
+  pub emit: Emit,
+  /// Extension `cc` recognizes the emitted code by
+  pub extension: &'static str,
+  /// Arguments before the executable and the emitted code
+  pub flags: &'static [&'static str],
+  /// Arguments after the emitted code, such as libraries
+  pub libs: &'static [&'static str],
+}
+
+impl Native {
+  pub fn emit(&self, source: &str) -> String {
+    let options = CompileOptions {
+      emit: self.emit,
+      ..CompileOptions::default()
+    };
+    let output = compile(source, &options);
+    assert!(output.succeeded(), "{:?}", output.diagnostics);
+    output.output.unwrap()
+  }
+
+  /// Builds the program and runs it, returning its output
+  pub fn run(&self, source: &str, name: &str) -> String {
+    let dir = env::temp_dir().join(format!("caravel-{}-{}", self.extension, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let code = dir.join(format!("{}.{}", name, self.extension));
+    let exe = dir.join(name);
+    fs::write(&code, self.emit(source)).unwrap();
+
+    let status = Command::new("cc")
+      .args(self.flags)
+      .arg("-o")
+      .arg(&exe)
+      .arg(&code)
+      .args(self.libs)
+      .status()
+      .unwrap();
+    assert!(status.success(), "cc failed on\n{}", self.emit(source));
+
+    let output = Command::new(&exe).output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+  }
+
+  /// Checks that each program prints the value the VM computes for
+  /// it, bit for bit or both NaN, or nothing if it has no value.
+  /// `test` names the calling test, and its executables
+  pub fn assert_agrees_with_the_vm<S: AsRef<str>>(&self, test: &str, sources: &[S]) {
+    if !cc_available(test) {
+      return;
+    }
+
+    for (i, source) in sources.iter().enumerate() {
+      let source = source.as_ref();
+      let native = self.run(source, &format!("{}{}", test, i));
+      match run_vm(source) {
+        Some(expected) => {
+          let actual: f64 = native.trim().parse().unwrap();
+          assert!(
+            actual.to_bits() == expected.to_bits() || actual.is_nan() && expected.is_nan(),
+            "{}: {} != {}",
+            source,
+            actual,
+            expected
+          );
+        }
+        None => assert_eq!(native, "", "{}", source),
+      }
+    }
+  }
+}
+
+fn run_vm(source: &str) -> Option<f64> {
+  let output = compile(source, &CompileOptions::default());
+  let program = bytecode::compile::compile(&output.ast.unwrap(), &output.resolution.unwrap());
+  vm::run(&program).unwrap()
+}
+
+/// Whether `cc` can be run. If it can't, `test` is reported as skipped,
+/// or fails if `CARAVEL_REQUIRE_CC` is set, e.g. on CI
+fn cc_available(test: &str) -> bool {
+  if Command::new("cc").arg("--version").output().is_ok() {
+    return true;
+  }
+
+  if env::var_os("CARAVEL_REQUIRE_CC").is_some() {
+    panic!("`cc` is required to run {}", test);
+  }
+  // Written to stderr directly, since the test harness
+  // only shows captured output of failing tests
+  let notice = format!(
+    "skipping {}: `cc` not found, native code was not tested\n",
+    test
+  );
+  io::stderr().write_all(notice.as_bytes()).unwrap();
+  false
+}
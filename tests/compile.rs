@@ -0,0 +1,60 @@
+use caravel::{compile, CompileOptions, CompileOutput, ErrorKind, Severity, Type};
+
+fn check(source: &str) -> CompileOutput {
+  compile(source, &CompileOptions::default())
+}
+
+#[test]
+fn runs_every_stage_of_a_valid_program() {
+  let output = check("let x: float = 2\nx * 3");
+  assert!(output.succeeded());
+  assert!(output.diagnostics.is_empty());
+  assert_eq!(output.tokens.unwrap().len(), 12);
+  let ast = output.ast.unwrap();
+  assert_eq!(ast[ast.root()].expressions.len(), 2);
+  assert_eq!(output.ty, Some(Type::Float));
+
+  let tree = output.output.unwrap();
+  assert!(tree.starts_with("`- Block"), "{}", tree);
+  assert!(!tree.contains('\x1b'), "{}", tree);
+}
+
+#[test]
+fn colors_the_output_only_when_asked_to() {
+  let options = CompileOptions {
+    color: true,
+    ..CompileOptions::default()
+  };
+  let tree = compile("1 + 2", &options).output.unwrap();
+  assert!(tree.contains("\x1b["), "{}", tree);
+}
+
+#[test]
+fn stops_at_the_first_failing_stage() {
+  let output = check("let x: float = $");
+  assert_eq!(output.diagnostics[0].kind(), ErrorKind::UnexpectedChar);
+  assert!(output.tokens.is_none() && output.ast.is_none());
+
+  let output = check("let x: float =");
+  assert_eq!(output.diagnostics[0].kind(), ErrorKind::UnexpectedToken);
+  assert!(output.tokens.is_some() && output.ast.is_none());
+
+  let output = check("let x: float = y");
+  assert!(!output.succeeded());
+  assert_eq!(output.diagnostics.len(), 1);
+  assert_eq!(output.diagnostics[0].kind(), ErrorKind::UndeclaredVariable);
+  assert_eq!(output.diagnostics[0].severity(), Severity::Error);
+  assert!(output.ast.is_some());
+  assert!(output.ty.is_none() && output.output.is_none());
+}
+
+#[test]
+fn reports_warnings_without_stopping() {
+  // The "о" is Cyrillic
+  let output = check("let tоtal: float = 1\ntоtal");
+  assert!(output.succeeded());
+  assert_eq!(output.diagnostics.len(), 1);
+  assert_eq!(output.diagnostics[0].severity(), Severity::Warning);
+  assert_eq!(output.ty, Some(Type::Float));
+  assert!(output.output.is_some());
+}
@@ -0,0 +1,88 @@
+use caravel::{compile, CompileOptions, ErrorKind};
+
+#[test]
+fn folds_literals_and_immutable_constants() {
+  let source = "\
+let a: float = 2
+let mut b: float = a * 3.5 + 1
+b = b - (a + 0.5) * 2
+b * (4 - 1)
+";
+  let output = compile(source, &CompileOptions::default());
+  assert!(output.diagnostics.is_empty());
+  assert_eq!(
+    output.output.unwrap(),
+    "\
+`- Block : float
+   |- Initialization : float
+   |  |- a
+   |  `- 2 : float
+   |- Initialization : float
+   |  |- mut b
+   |  `- 8 : float
+   |- Reassignment : float
+   |  |- b
+   |  `- Subtract : float
+   |     |- b : float
+   |     `- 5 : float
+   `- Multiply : float
+      |- b : float
+      `- 3 : float
+"
+  );
+}
+
+#[test]
+fn warns_about_constant_zero_divisors() {
+  let source = "\
+let zero: float = 1 - 1
+let mut x: float = 4
+x = x / zero
+x % (2 * 0)
+";
+  let output = compile(source, &CompileOptions::default());
+  let warnings: Vec<(ErrorKind, &str, usize)> = output
+    .diagnostics
+    .iter()
+    .map(|warning| (warning.kind(), warning.msg(), warning.pos().start_pos.line))
+    .collect();
+
+  assert_eq!(
+    warnings,
+    [
+      (ErrorKind::DivisionByZero, "division by zero", 2),
+      (ErrorKind::DivisionByZero, "modulo by zero", 3),
+    ]
+  );
+}
+
+#[test]
+fn keeps_negative_results_unfolded() {
+  // There are no negative literals, so only the
+  // positive product of the two negative terms folds
+  let source = "let a: float = 1 - 3\na * 2 - (0 - 1) * (0 - 1) + 0 * (0 - 1)";
+  let output = compile(source, &CompileOptions::default());
+  assert!(output.diagnostics.is_empty());
+  assert_eq!(
+    output.output.unwrap(),
+    "\
+`- Block : float
+   |- Initialization : float
+   |  |- a
+   |  `- Subtract : float
+   |     |- 1 : float
+   |     `- 3 : float
+   `- Add : float
+      |- Subtract : float
+      |  |- Multiply : float
+      |  |  |- a : float
+      |  |  `- 2 : float
+      |  `- 1 : float
+      `- Multiply : float
+         |- 0 : float
+         `- Subtract : float
+            |- 0 : float
+            `- 1 : float
+"
+  );
+}
@@ -0,0 +1,147 @@
+use caravel::backend::print::print;
+use caravel::formatter::format;
+use caravel::{ErrorKind, Lexer, Parser};
+use proptest::collection::vec;
+use proptest::option;
+use proptest::prelude::*;
+
+fn tree(source: &str) -> String {
+  let tokens = Lexer::new(source).lex().unwrap();
+  print(&Parser::new(&tokens).parse().unwrap(), None, false)
+}
+
+#[test]
+fn formats_spacing_indentation_and_comments() {
+  let source = "\
+// header
+
+
+let  mut a:float=1+2*3   // why
+let v :void={ let b: float=(a-1)%2
+
+      // inner
+  b}
+{
+}
+a=a/2";
+
+  assert_eq!(
+    format(source).unwrap(),
+    "\
+// header
+
+let mut a: float = 1 + 2 * 3 // why
+let v: void = {
+  let b: float = (a - 1) % 2
+
+  // inner
+  b
+}
+{}
+a = a / 2
+"
+  );
+}
+
+#[test]
+fn refuses_to_format_invalid_programs() {
+  for (source, msg) in [
+    ("let x = float", "expected ':'"),
+    ("(1 + 2 3", "expected ')'"),
+  ] {
+    let error = format(source).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedToken, "{}", source);
+    assert_eq!(error.msg(), msg, "{}", source);
+  }
+}
+
+// Strategies for syntactically valid, but arbitrarily spaced programs
+fn ws() -> impl Strategy<Value = &'static str> {
+  prop_oneof![Just(""), Just(" "), Just("   "), Just("\t")]
+}
+
+fn ident() -> impl Strategy<Value = String> {
+  "[a-c]|é|_x1"
+}
+
+fn term() -> impl Strategy<Value = String> {
+  let leaf = prop_oneof![ident(), "[0-9]{1,3}(\\.[0-9]{1,2})?"];
+  leaf.prop_recursive(3, 16, 2, |inner| {
+    prop_oneof![
+      (inner.clone(), ws(), "[-+*/%]", ws(), inner.clone())
+        .prop_map(|(lhs, a, op, b, rhs)| format!("{}{}{}{}{}", lhs, a, op, b, rhs)),
+      (ws(), inner, ws()).prop_map(|(a, term, b)| format!("({}{}{})", a, term, b)),
+    ]
+  })
+}
+
+fn line(statement: BoxedStrategy<String>) -> impl Strategy<Value = String> {
+  (
+    ws(),
+    option::of(statement),
+    ws(),
+    option::of("// [a-z ]{0,8}"),
+  )
+    .prop_map(|(a, statement, b, comment)| {
+      format!(
+        "{}{}{}{}\n",
+        a,
+        statement.unwrap_or_default(),
+        b,
+        comment.unwrap_or_default()
+      )
+    })
+}
+
+fn statement() -> BoxedStrategy<String> {
+  let simple = prop_oneof![
+    term(),
+    (ident(), ws(), ws(), term())
+      .prop_map(|(ident, a, b, val)| format!("{}{}={}{}", ident, a, b, val)),
+    (
+      option::of("mut "),
+      ident(),
+      ws(),
+      ws(),
+      option::of((ws(), ws(), term()))
+    )
+      .prop_map(|(mutable, ident, a, b, val)| {
+        let val = val.map_or(String::new(), |(c, d, val)| format!("{}={}{}", c, d, val));
+        format!(
+          "let {}{}{}:{}float{}",
+          mutable.unwrap_or_default(),
+          ident,
+          a,
+          b,
+          val
+        )
+      }),
+  ];
+
+  simple
+    .prop_recursive(3, 24, 3, |inner| {
+      let block = vec(line(inner), 0..4)
+        .prop_map(|lines| format!("{{{}}}", lines.concat()))
+        .boxed();
+      prop_oneof![
+        block.clone(),
+        (ident(), ws(), block)
+          .prop_map(|(ident, a, block)| format!("let {}: void ={}{}", ident, a, block)),
+      ]
+    })
+    .boxed()
+}
+
+fn program() -> impl Strategy<Value = String> {
+  vec(line(statement()), 0..8).prop_map(|lines| lines.concat())
+}
+
+proptest! {
+  #[test]
+  fn formatting_is_idempotent_and_preserves_the_ast(source in program()) {
+    let formatted = format(&source).unwrap();
+
+    prop_assert_eq!(format(&formatted).unwrap(), formatted.clone());
+    prop_assert_eq!(tree(&formatted), tree(&source));
+  }
+}
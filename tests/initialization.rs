@@ -0,0 +1,44 @@
+use caravel::{compile, CompileOptions, ErrorKind, Severity};
+
+fn error(source: &str) -> Option<(ErrorKind, String, usize)> {
+  let output = compile(source, &CompileOptions::default());
+  output
+    .diagnostics
+    .iter()
+    .find(|diagnostic| diagnostic.severity() == Severity::Error)
+    .map(|error| {
+      (
+        error.kind(),
+        error.msg().to_owned(),
+        error.pos().start_pos.line,
+      )
+    })
+}
+
+#[test]
+fn rejects_reads_before_assignment() {
+  assert_eq!(
+    error("let x: float\nlet y: float = x + 1\ny"),
+    Some((
+      ErrorKind::UninitializedVariable,
+      r#"use of possibly uninitialized variable "x""#.to_owned(),
+      1
+    ))
+  );
+  assert_eq!(
+    error("let x: float\nx = x * 2\nx").map(|error| error.0),
+    Some(ErrorKind::UninitializedVariable)
+  );
+}
+
+#[test]
+fn follows_assignments_across_blocks() {
+  assert_eq!(error("let x: float\n{\n  x = 1\n}\nx"), None);
+
+  // Variables outlive the block declaring them
+  assert_eq!(error("{\n  let x: float = 1\n}\nx"), None);
+  assert_eq!(
+    error("{\n  let x: float\n}\nx").map(|error| error.2),
+    Some(3)
+  );
+}
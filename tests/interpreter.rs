@@ -0,0 +1,70 @@
+use caravel::bytecode::{self, vm};
+use caravel::interpreter::{Interpreter, Value};
+use caravel::{compile, Analyzer, CompileOptions, Lexer, Parser};
+
+/// Evaluates `source` with the tree-walking interpreter, unfolded
+fn interpret(source: &str) -> Value {
+  let tokens = Lexer::new(source).lex().unwrap();
+  let ast = Parser::new(&tokens).parse().unwrap();
+  Analyzer::new(&tokens, &ast).analyze().unwrap();
+  Interpreter::new(&tokens, &ast).run().unwrap()
+}
+
+/// Runs `source` on the bytecode virtual machine
+fn run_vm(source: &str) -> Option<f64> {
+  let output = compile(source, &CompileOptions::default());
+  assert!(output.succeeded(), "{:?}", output.diagnostics);
+  let program = bytecode::compile::compile(&output.ast.unwrap(), &output.resolution.unwrap());
+  vm::run(&program).unwrap()
+}
+
+#[test]
+fn evaluates_programs() {
+  for (source, expected) in [
+    ("", Value::Void),
+    ("let v: void = {}\nv", Value::Void),
+    ("1 + 2 * 3 - 4 / 8", Value::Float(6.5)),
+    ("(1 + 2) * 3 % 4", Value::Float(1.0)),
+    ("(0 - 7) % 3", Value::Float(-1.0)),
+    (
+      "let mut x: float = 1\n{\n  let y: float = x * 3\n  x = y - x\n  x % 3 + 0.5\n}",
+      Value::Float(2.5),
+    ),
+    ("let mut a: float\na = 2\na = a * a\na", Value::Float(4.0)),
+  ] {
+    assert_eq!(interpret(source), expected, "{:?}", source);
+  }
+}
+
+#[test]
+fn follows_ieee_754_for_division_by_zero() {
+  assert_eq!(interpret("1 / 0"), Value::Float(f64::INFINITY));
+  assert!(matches!(interpret("0 / 0"), Value::Float(value) if value.is_nan()));
+  assert!(matches!(interpret("7 % 0 - 1"), Value::Float(value) if value.is_nan()));
+}
+
+#[test]
+fn agrees_with_the_vm() {
+  let sources = [
+    "",
+    "let v: void = {}\nv",
+    "1 + 2 * 3 - 4 / 8",
+    "(1 + 2) * 3 % 4",
+    "(0 - 7) % 3",
+    "7 % 0 - 1",
+    "1 / 0",
+    "0 / 0",
+    "let mut x: float = 1\n{\n  let y: float = x * 3\n  x = y - x\n}\nx % 3 + 0.5",
+    "let mut a: float\na = 2\na = a * a\na",
+    "let a: float = {\n  let b: float = 4\n  b / 3\n}\na * 3",
+  ];
+
+  for source in sources {
+    match (interpret(source), run_vm(source)) {
+      (Value::Void, None) => (),
+      (Value::Float(interpreted), Some(ran))
+        if interpreted.to_bits() == ran.to_bits() || interpreted.is_nan() && ran.is_nan() => {}
+      (interpreted, ran) => panic!("{:?}: interpreted {:?}, ran {:?}", source, interpreted, ran),
+    }
+  }
+}
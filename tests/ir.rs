@@ -0,0 +1,123 @@
+use caravel::ir::verify::verify;
+use caravel::ir::*;
+use caravel::{compile, CompileOptions, Emit, Type};
+
+fn emit_ir(source: &str) -> String {
+  let options = CompileOptions {
+    emit: Emit::Ir,
+    ..CompileOptions::default()
+  };
+  compile(source, &options).output.unwrap()
+}
+
+#[test]
+fn lowers_to_loads_stores_and_registers() {
+  let source = "\
+let mut x: float = 1 + 1
+let v: void = {}
+{
+  let y: float = x * 3
+  x = y - x
+}
+{
+  let w: void = v
+}
+x
+";
+  assert_eq!(
+    emit_ir(source),
+    "\
+fn main() -> float {
+  var @x: float
+  var @v: void
+  var @y: float
+  var @w: void
+
+bb0:
+  %0: float = const 2
+  store @x, %0
+  %1: float = load @x
+  %2: float = const 3
+  %3: float = mul %1, %2
+  store @y, %3
+  %4: float = load @y
+  %5: float = load @x
+  %6: float = sub %4, %5
+  store @x, %6
+  %7: float = load @x
+  ret %7
+}
+"
+  );
+  assert_eq!(emit_ir(""), "fn main() -> void {\nbb0:\n  ret\n}\n");
+}
+
+fn function(instructions: Vec<Instruction>, terminator: Terminator) -> Function {
+  Function {
+    ret: Type::Float,
+    registers: vec![Type::Float, Type::Float],
+    variables: vec![Variable {
+      name: "v".to_owned(),
+      typ: Type::Void,
+    }],
+    blocks: vec![BasicBlock {
+      instructions,
+      terminator,
+    }],
+  }
+}
+
+#[test]
+fn verifier_rejects_malformed_functions() {
+  let ret = Terminator::Return(Some(Reg(0)));
+  let valid = function(
+    vec![
+      Instruction::Const(Reg(0), 1.0),
+      Instruction::Binary(Reg(1), BinaryOp::Add, Reg(0), Reg(0)),
+    ],
+    ret.clone(),
+  );
+  assert_eq!(verify(&valid), Ok(()));
+
+  let cases = [
+    (
+      function(vec![], ret.clone()),
+      "bb0, terminator: %0 is used before it is assigned",
+    ),
+    (
+      function(
+        vec![
+          Instruction::Const(Reg(0), 1.0),
+          Instruction::Const(Reg(0), 2.0),
+        ],
+        ret.clone(),
+      ),
+      "bb0, instruction 1: %0 is assigned twice",
+    ),
+    (
+      function(
+        vec![
+          Instruction::Const(Reg(0), 1.0),
+          Instruction::Store(Var(0), Reg(0)),
+        ],
+        ret.clone(),
+      ),
+      "bb0, instruction 1: %0 has type float, where a void is expected",
+    ),
+    (
+      function(vec![Instruction::Load(Reg(2), Var(0))], ret),
+      "bb0, instruction 0: missing register %2",
+    ),
+    (
+      function(vec![], Terminator::Jump(BlockId(1))),
+      "bb0, terminator: jump to missing block bb1",
+    ),
+    (
+      function(vec![], Terminator::Return(None)),
+      "bb0, terminator: missing float return value",
+    ),
+  ];
+  for (function, error) in &cases {
+    assert_eq!(verify(function), Err(error.to_string()));
+  }
+}
@@ -0,0 +1,307 @@
+use caravel::lsp::{read_message, serve, Server};
+use serde_json::{json, Value};
+use std::io::Cursor;
+
+const URI: &str = "file:///main.cv";
+
+struct Client {
+  server: Server,
+  next_id: u64,
+}
+
+impl Client {
+  fn new() -> Self {
+    Self {
+      server: Server::new(),
+      next_id: 0,
+    }
+  }
+
+  fn request(&mut self, method: &str, params: Value) -> Value {
+    self.next_id += 1;
+    let replies = self.server.handle(&json!({
+      "jsonrpc": "2.0",
+      "id": self.next_id,
+      "method": method,
+      "params": params,
+    }));
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0]["id"], self.next_id);
+    replies[0].clone()
+  }
+
+  fn notify(&mut self, method: &str, params: Value) -> Vec<Value> {
+    self.server.handle(&json!({
+      "jsonrpc": "2.0",
+      "method": method,
+      "params": params,
+    }))
+  }
+
+  fn open(&mut self, text: &str) -> Value {
+    let replies = self.notify(
+      "textDocument/didOpen",
+      json!({ "textDocument": { "uri": URI, "languageId": "caravel", "version": 1, "text": text } }),
+    );
+    diagnostics(&replies)
+  }
+
+  fn at(&mut self, method: &str, line: u64, character: u64) -> Value {
+    self.request(
+      method,
+      json!({
+        "textDocument": { "uri": URI },
+        "position": { "line": line, "character": character },
+      }),
+    )["result"]
+      .clone()
+  }
+}
+
+fn diagnostics(replies: &[Value]) -> Value {
+  assert_eq!(replies.len(), 1);
+  assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+  assert_eq!(replies[0]["params"]["uri"], URI);
+  replies[0]["params"]["diagnostics"].clone()
+}
+
+fn range(start: (u64, u64), end: (u64, u64)) -> Value {
+  json!({
+    "start": { "line": start.0, "character": start.1 },
+    "end": { "line": end.0, "character": end.1 },
+  })
+}
+
+fn labels(completions: &Value) -> Vec<&str> {
+  let mut labels: Vec<&str> = completions
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|item| item["label"].as_str().unwrap())
+    .collect();
+  labels.sort_unstable();
+  labels
+}
+
+const SOURCE: &str = "\
+let mut x: float = 1
+let v: float = {
+  let y: float = x * 2
+
+  y
+}
+x = x + v
+";
+
+#[test]
+fn initialize_advertises_capabilities() {
+  let capabilities = Client::new().request("initialize", json!({ "capabilities": {} }))["result"]
+    ["capabilities"]
+    .clone();
+
+  assert_eq!(capabilities["textDocumentSync"], 1);
+  assert_eq!(capabilities["hoverProvider"], true);
+  assert_eq!(capabilities["definitionProvider"], true);
+  assert_eq!(capabilities["documentSymbolProvider"], true);
+  assert_eq!(capabilities["renameProvider"], true);
+  assert!(capabilities["completionProvider"].is_object());
+}
+
+#[test]
+fn publishes_diagnostics_on_open_and_change() {
+  let mut client = Client::new();
+
+  assert_eq!(client.open(SOURCE), json!([]));
+
+  let replies = client.notify(
+    "textDocument/didChange",
+    json!({
+      "textDocument": { "uri": URI, "version": 2 },
+      "contentChanges": [{ "text": "let a: float = 1\na = é\n" }],
+    }),
+  );
+  assert_eq!(
+    diagnostics(&replies),
+    json!([{
+      "range": range((1, 4), (1, 5)),
+      "severity": 1,
+      "code": "E0003",
+      "source": "caravel",
+      "message": "use of undeclared variable \"é\"",
+      "relatedInformation": [],
+    }])
+  );
+
+  let replies = client.notify(
+    "textDocument/didChange",
+    json!({
+      "textDocument": { "uri": URI, "version": 3 },
+      "contentChanges": [{ "text": "let a: float = 1\na = 2\n" }],
+    }),
+  );
+  let diagnostic = diagnostics(&replies)[0].clone();
+  assert_eq!(diagnostic["code"], "E0012");
+  assert_eq!(
+    diagnostic["message"],
+    "cannot reassign immutable variable \"a\"\nhelp: consider making this binding mutable: `let mut a`"
+  );
+  assert_eq!(
+    diagnostic["relatedInformation"],
+    json!([{
+      "location": { "uri": URI, "range": range((0, 4), (0, 5)) },
+      "message": "\"a\" is declared here",
+    }])
+  );
+
+  let replies = client.notify(
+    "textDocument/didClose",
+    json!({ "textDocument": { "uri": URI } }),
+  );
+  assert_eq!(diagnostics(&replies), json!([]));
+}
+
+#[test]
+fn hover_shows_declared_type() {
+  let mut client = Client::new();
+  client.open(SOURCE);
+
+  let hover = client.at("textDocument/hover", 2, 17);
+  assert_eq!(hover["contents"]["value"], "```caravel\nx: float\n```");
+  assert_eq!(hover["range"], range((2, 17), (2, 18)));
+
+  assert_eq!(client.at("textDocument/hover", 0, 13), Value::Null);
+}
+
+#[test]
+fn definition_points_at_declaration() {
+  let mut client = Client::new();
+  client.open(SOURCE);
+
+  assert_eq!(
+    client.at("textDocument/definition", 6, 4),
+    json!({ "uri": URI, "range": range((0, 8), (0, 9)) })
+  );
+}
+
+#[test]
+fn completion_lists_earlier_declarations() {
+  let mut client = Client::new();
+  client.open(SOURCE);
+
+  assert_eq!(
+    labels(&client.at("textDocument/completion", 2, 0)),
+    ["v", "x"]
+  );
+  // Including those of blocks that have ended
+  assert_eq!(
+    labels(&client.at("textDocument/completion", 6, 0)),
+    ["v", "x", "y"]
+  );
+}
+
+#[test]
+fn document_symbols_list_declarations() {
+  let mut client = Client::new();
+  client.open(SOURCE);
+
+  let symbols = client.request(
+    "textDocument/documentSymbol",
+    json!({ "textDocument": { "uri": URI } }),
+  )["result"]
+    .clone();
+  let symbols: Vec<(&str, &str)> = symbols
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|symbol| {
+      (
+        symbol["name"].as_str().unwrap(),
+        symbol["detail"].as_str().unwrap(),
+      )
+    })
+    .collect();
+
+  assert_eq!(symbols, [("x", "float"), ("y", "float"), ("v", "float")]);
+}
+
+#[test]
+fn rename_edits_every_occurrence() {
+  let mut client = Client::new();
+  client.open(SOURCE);
+
+  let params = |new_name: &str| {
+    json!({
+      "textDocument": { "uri": URI },
+      "position": { "line": 6, "character": 0 },
+      "newName": new_name,
+    })
+  };
+
+  let edit = client.request("textDocument/rename", params("z"))["result"].clone();
+  let ranges: Vec<Value> = edit["changes"][URI]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|edit| {
+      assert_eq!(edit["newText"], "z");
+      edit["range"].clone()
+    })
+    .collect();
+  assert_eq!(
+    ranges,
+    [
+      range((0, 8), (0, 9)),
+      range((2, 17), (2, 18)),
+      range((6, 0), (6, 1)),
+      range((6, 4), (6, 5)),
+    ]
+  );
+
+  let error = client.request("textDocument/rename", params("y"))["error"].clone();
+  assert_eq!(error["code"], -32803);
+}
+
+#[test]
+fn unknown_methods_and_shutdown() {
+  let mut client = Client::new();
+
+  assert_eq!(
+    client.request("textDocument/implementation", json!({}))["error"]["code"],
+    -32601
+  );
+  assert_eq!(
+    client.request("shutdown", Value::Null)["result"],
+    Value::Null
+  );
+  assert_eq!(
+    client.request("textDocument/hover", json!({}))["error"]["code"],
+    -32600
+  );
+}
+
+#[test]
+fn serves_framed_messages_until_exit() {
+  let messages = [
+    json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+    json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" }),
+    json!({ "jsonrpc": "2.0", "method": "exit" }),
+    json!({ "jsonrpc": "2.0", "id": 3, "method": "initialize", "params": {} }),
+  ];
+  let input: String = messages
+    .iter()
+    .map(|message| {
+      let body = message.to_string();
+      format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    })
+    .collect();
+
+  let mut output = Vec::new();
+  serve(Cursor::new(input), &mut output).unwrap();
+
+  let mut output = Cursor::new(output);
+  let mut ids = Vec::new();
+  while let Some(reply) = read_message(&mut output).unwrap() {
+    ids.push(reply["id"].clone());
+  }
+  assert_eq!(ids, [json!(1), json!(2)]);
+}
@@ -0,0 +1,39 @@
+use caravel::{compile, format_error, CompileOptions, ErrorKind};
+
+fn errors(source: &str) -> Vec<String> {
+  compile(source, &CompileOptions::default())
+    .diagnostics
+    .iter()
+    .map(|error| format_error(error, source, false))
+    .collect()
+}
+
+#[test]
+fn rejects_reassigning_immutable_variables() {
+  let source = "let total: float = 1\n{\n  total = total + 2\n}\ntotal";
+  let output = compile(source, &CompileOptions::default());
+  assert_eq!(output.diagnostics[0].kind(), ErrorKind::ImmutableAssignment);
+
+  assert_eq!(
+    errors(source),
+    ["\
+error[E0012]: assignment to immutable variable
+        total = total + 2
+        ^^^^^^^^^^^^^^^^^ cannot reassign immutable variable \"total\"
+      let total: float = 1
+          ----- \"total\" is declared here
+      help: consider making this binding mutable: `let mut total`
+      run `caravel --explain E0012` for more information
+"]
+  );
+}
+
+#[test]
+fn allows_mutable_and_deferred_assignments() {
+  assert!(errors("let mut total: float = 1\ntotal = total + 2\ntotal").is_empty());
+  assert!(errors("let total: float\ntotal = 2\ntotal").is_empty());
+
+  let errors = errors("let total: float\ntotal = 2\ntotal = 3\ntotal");
+  assert_eq!(errors.len(), 1);
+  assert!(errors[0].starts_with("error[E0012]"));
+}
@@ -0,0 +1,69 @@
+use caravel::rename::{apply, offset, rename};
+use caravel::ErrorKind;
+
+const SOURCE: &str = "\
+let width: float = 3
+let area: float = {
+  let height: float = 4
+  width * height
+}
+{
+  let mut h: float = 1
+  h = h * 2
+}
+";
+
+fn renamed(line: usize, column: usize, new_name: &str) -> Result<String, String> {
+  let offset = offset(SOURCE, line, column).unwrap();
+  match rename(SOURCE, offset, new_name) {
+    Ok(Some(spans)) => Ok(apply(SOURCE, &spans, new_name)),
+    Ok(None) => Err("no variable".to_owned()),
+    Err(error) => {
+      assert_eq!(error.kind(), ErrorKind::InvalidRename);
+      Err(format!("{} at {}", error.msg(), error.pos().start_pos.line))
+    }
+  }
+}
+
+#[test]
+fn renames_only_the_resolved_variable() {
+  assert_eq!(
+    renamed(7, 2, "n").unwrap(),
+    SOURCE
+      .replace("let mut h:", "let mut n:")
+      .replace("h = h", "n = n")
+  );
+  // From a use
+  assert_eq!(
+    renamed(3, 10, "depth").unwrap(),
+    SOURCE.replace("height", "depth")
+  );
+}
+
+#[test]
+fn refuses_conflicting_names() {
+  assert_eq!(
+    renamed(2, 6, "width"),
+    Err(r#"renaming "height" to "width" would conflict with this declaration at 0"#.to_owned())
+  );
+  assert_eq!(
+    renamed(0, 4, "height"),
+    Err(r#"renaming "width" to "height" would conflict with this declaration at 2"#.to_owned())
+  );
+  // Even if the other variable is declared in another block
+  assert_eq!(
+    renamed(3, 10, "h"),
+    Err(r#"renaming "height" to "h" would conflict with this declaration at 6"#.to_owned())
+  );
+  assert_eq!(
+    renamed(0, 4, "let"),
+    Err(r#""let" is not a valid identifier at 0"#.to_owned())
+  );
+}
+
+#[test]
+fn finds_nothing_outside_variables() {
+  assert_eq!(renamed(0, 0, "x"), Err("no variable".to_owned()));
+  assert_eq!(renamed(0, 19, "x"), Err("no variable".to_owned()));
+  assert_eq!(offset(SOURCE, 0, 21), None);
+}
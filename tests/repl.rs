@@ -0,0 +1,135 @@
+use caravel::repl::{Reply, Session};
+
+/// Output of a successful input
+fn output(session: &mut Session, input: &str) -> Option<String> {
+  let reply = session.eval(input);
+  assert!(reply.diagnostics.is_empty(), "{:?}", reply.diagnostics);
+  reply.output
+}
+
+/// Code of the error an input fails with
+fn error(reply: Reply) -> &'static str {
+  assert_eq!(reply.output, None);
+  assert_eq!(reply.diagnostics.len(), 1, "{:?}", reply.diagnostics);
+  reply.diagnostics[0].kind().code()
+}
+
+#[test]
+fn keeps_bindings_between_inputs() {
+  let mut session = Session::new(false);
+  assert_eq!(
+    output(&mut session, "let mut a: float = 2"),
+    Some("2 : float\n".into())
+  );
+  assert_eq!(
+    output(&mut session, "a = a * 3"),
+    Some("6 : float\n".into())
+  );
+  assert_eq!(
+    output(&mut session, "let b: float = {\n  a + 1\n}"),
+    Some("7 : float\n".into())
+  );
+  assert_eq!(
+    output(&mut session, "a % b - 1"),
+    Some("5 : float\n".into())
+  );
+  assert_eq!(output(&mut session, "let v: void = {}"), None);
+  assert_eq!(output(&mut session, "v"), None);
+}
+
+#[test]
+fn shows_types_and_trees_without_evaluating() {
+  let mut session = Session::new(false);
+  output(&mut session, "let mut a: float = 2");
+
+  assert_eq!(output(&mut session, ":type a = 5"), Some("float\n".into()));
+  assert_eq!(output(&mut session, ":type {}"), Some("void\n".into()));
+  assert_eq!(
+    output(&mut session, ":ast a + 1"),
+    Some("`- Block : float\n   `- Add : float\n      |- a : float\n      `- 1 : float\n".into())
+  );
+  // Trees of ill-typed expressions are shown without types
+  assert_eq!(
+    output(&mut session, ":ast b"),
+    Some("`- Block\n   `- b\n".into())
+  );
+  assert_eq!(output(&mut session, "a"), Some("2 : float\n".into()));
+
+  // Declarations in `:type` don't persist
+  output(&mut session, ":type let c: float = 1");
+  assert_eq!(error(session.eval("c")), "E0003");
+}
+
+#[test]
+fn positions_command_diagnostics_in_the_original_input() {
+  let mut session = Session::new(false);
+  let reply = session.eval(":type 1 + b");
+  assert_eq!(reply.diagnostics[0].pos().start_pos.column, 10);
+}
+
+#[test]
+fn resets_and_explains_itself() {
+  let mut session = Session::new(false);
+  output(&mut session, "let a: float = 1");
+  assert_eq!(
+    output(&mut session, ":reset"),
+    Some("session reset\n".into())
+  );
+  assert_eq!(error(session.eval("a")), "E0003");
+  assert_eq!(
+    output(&mut session, "let a: float = 2"),
+    Some("2 : float\n".into())
+  );
+
+  let help = output(&mut session, ":help").unwrap();
+  for command in [":type", ":ast", ":reset", ":help"] {
+    assert!(help.contains(command), "{}", help);
+  }
+  assert_eq!(
+    output(&mut session, ":nope"),
+    Some("unknown command `:nope`, see `:help`\n".into())
+  );
+}
+
+#[test]
+fn recovers_from_errors_without_keeping_their_declarations() {
+  let mut session = Session::new(false);
+  output(&mut session, "let a: float = 1");
+
+  assert_eq!(error(session.eval("let b: float = a +")), "E0002");
+  assert_eq!(error(session.eval("let b: float = 2\nb + zz")), "E0003");
+  assert_eq!(error(session.eval("let c: float\nc")), "E0008");
+
+  // Neither half-declared variable exists, so both names are free again
+  assert_eq!(error(session.eval("b")), "E0003");
+  assert_eq!(
+    output(&mut session, "let b: float = 3"),
+    Some("3 : float\n".into())
+  );
+  assert_eq!(
+    output(&mut session, "let c: float = 4"),
+    Some("4 : float\n".into())
+  );
+  assert_eq!(
+    output(&mut session, "a + b + c"),
+    Some("8 : float\n".into())
+  );
+}
+
+#[test]
+fn waits_for_blocks_to_be_closed() {
+  for input in [
+    "",
+    "a",
+    "{}",
+    "{ { } }",
+    "let a: float = {\n1\n}",
+    "}",
+    "{ $",
+  ] {
+    assert!(Session::is_complete(input), "{:?}", input);
+  }
+  for input in ["{", "{ {}", "let a: float = {\n1", "{ { }\n", "{ // }"] {
+    assert!(!Session::is_complete(input), "{:?}", input);
+  }
+}
@@ -0,0 +1,51 @@
+use caravel::resolver::{resolve, SymbolId};
+use caravel::{Lexer, Parser};
+
+#[test]
+fn links_uses_to_declarations() {
+  let source = "\
+let a: float = 1
+{
+  let b: float = a
+  b
+}
+{
+  let b: float = 2
+  a = b
+}
+";
+  let tokens = Lexer::new(source).lex().unwrap();
+  let resolution = resolve(&Parser::new(&tokens).parse().unwrap());
+
+  let names: Vec<(&str, &str)> = resolution
+    .declarations
+    .iter()
+    .map(|declaration| (&declaration.name[..], &declaration.type_ident[..]))
+    .collect();
+  assert_eq!(names, [("a", "float"), ("b", "float"), ("b", "float")]);
+
+  let a = SymbolId(0);
+  let uses = resolution.uses_of(a);
+  assert_eq!(uses.len(), 2);
+  for token in &uses {
+    assert_eq!(resolution.symbol_at(*token), Some(a));
+  }
+  for (i, declaration) in resolution.declarations.iter().enumerate() {
+    assert_eq!(resolution.symbol_at(declaration.token), Some(SymbolId(i)));
+  }
+  // The `let` of the first declaration
+  assert_eq!(resolution.symbol_at(1), None);
+
+  // Each block's `b` is a separate variable
+  assert_eq!(resolution.uses_of(SymbolId(1)).len(), 1);
+  assert_eq!(resolution.uses_of(SymbolId(2)).len(), 1);
+}
+
+#[test]
+fn leaves_undeclared_uses_unresolved() {
+  let tokens = Lexer::new("let a: float = b\nc = a").lex().unwrap();
+  let resolution = resolve(&Parser::new(&tokens).parse().unwrap());
+
+  assert_eq!(resolution.uses.len(), 1);
+  assert_eq!(resolution.uses_of(SymbolId(0)).len(), 1);
+}
@@ -0,0 +1,61 @@
+use caravel::{compile, CompileOptions, Emit};
+use serde_json::{json, Value};
+
+fn emit(source: &str, emit: Emit) -> Option<String> {
+  let options = CompileOptions {
+    emit,
+    ..CompileOptions::default()
+  };
+  compile(source, &options).output
+}
+
+#[test]
+fn dumps_tokens_with_positions() {
+  let source = "let mut é: float = 1.5 // hi\n{ é = é % 2 }\n";
+  assert_eq!(
+    emit(source, Emit::Tokens).unwrap(),
+    r#"LBracket     ""      1:1  0 synthetic
+Let          "let"   1:1  3
+Mut          "mut"   1:5  3
+Identifier   "é"     1:9  2
+Colon        ":"     1:10 1
+Identifier   "float" 1:12 5
+Assignment   "="     1:18 1
+FloatLiteral "1.5"   1:20 3
+Eol          "\n"    1:29 1
+LBracket     "{"     2:1  1
+Identifier   "é"     2:3  2
+Assignment   "="     2:5  1
+Identifier   "é"     2:7  2
+Modulo       "%"     2:9  1
+FloatLiteral "2"     2:11 1
+RBracket     "}"     2:13 1
+Eol          "\n"    2:14 1
+RBracket     ""      3:1  0 synthetic
+"#
+  );
+}
+
+#[test]
+fn dumps_tokens_as_json() {
+  let document: Value = serde_json::from_str(&emit("x\n", Emit::TokensJson).unwrap()).unwrap();
+  assert_eq!(
+    document,
+    json!({
+      "version": 1,
+      "tokens": [
+        { "kind": "LBracket", "lexeme": "", "line": 1, "column": 1, "offset": 0, "len": 0, "synthetic": true },
+        { "kind": "Identifier", "lexeme": "x", "line": 1, "column": 1, "offset": 0, "len": 1, "synthetic": false },
+        { "kind": "Eol", "lexeme": "\n", "line": 1, "column": 2, "offset": 1, "len": 1, "synthetic": false },
+        { "kind": "RBracket", "lexeme": "", "line": 2, "column": 1, "offset": 2, "len": 0, "synthetic": true },
+      ],
+    })
+  );
+}
+
+#[test]
+fn dumps_tokens_of_programs_that_do_not_parse() {
+  let dump = emit("let = }", Emit::Tokens).unwrap();
+  assert_eq!(dump.lines().count(), 5);
+  assert!(emit("let x: float = $", Emit::Tokens).is_none());
+}
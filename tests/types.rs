@@ -0,0 +1,71 @@
+use caravel::backend::print::print;
+use caravel::parser::ast::Expression;
+use caravel::{compile, Analyzer, CompileOptions, Lexer, Parser, Type};
+
+#[test]
+fn prints_the_type_of_each_node() {
+  let source = "\
+let mut x: float = 1
+let v: void = {}
+x = x * 2 + 1
+";
+  let output = compile(source, &CompileOptions::default());
+  assert!(output.succeeded());
+  assert_eq!(
+    output.output.unwrap(),
+    "\
+`- Block : float
+   |- Initialization : float
+   |  |- mut x
+   |  `- 1 : float
+   |- Initialization : void
+   |  |- v
+   |  `- Block : void
+   `- Reassignment : float
+      |- x
+      `- Add : float
+         |- Multiply : float
+         |  |- x : float
+         |  `- 2 : float
+         `- 1 : float
+"
+  );
+}
+
+#[test]
+fn looks_up_types_by_node() {
+  let source = "\
+let a: float = 2
+{
+  let b: void = {}
+  b
+}
+";
+  let tokens = Lexer::new(source).lex().unwrap();
+  let ast = Parser::new(&tokens).parse().unwrap();
+  let mut analyzer = Analyzer::new(&tokens, &ast);
+  assert_eq!(analyzer.analyze().unwrap(), Type::Void);
+
+  let types = analyzer.into_types();
+  let program = &ast[ast.root()];
+  assert_eq!(types.of(ast.root()), Some(Type::Void));
+  assert_eq!(types.of(program.expressions[0]), Some(Type::Float));
+  match ast[program.expressions[1]] {
+    Expression::Block(inner) => {
+      let inner = &ast[inner].expressions;
+      assert_eq!(types.of(inner[0]), Some(Type::Void));
+      assert_eq!(types.of(inner[1]), Some(Type::Void));
+    }
+    expr => panic!("expected a block, found {:?}", expr),
+  }
+}
+
+#[test]
+fn prints_untyped_trees_without_types() {
+  let tokens = Lexer::new("1 + a").lex().unwrap();
+  let ast = Parser::new(&tokens).parse().unwrap();
+  assert_eq!(
+    print(&ast, None, false),
+    "`- Block\n   `- Add\n      |- 1\n      `- a\n"
+  );
+}
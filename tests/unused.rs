@@ -0,0 +1,78 @@
+use caravel::{compile, CompileOptions, ErrorKind, Severity};
+
+fn warnings(source: &str) -> Vec<(ErrorKind, String, usize)> {
+  let output = compile(source, &CompileOptions::default());
+  assert!(output.succeeded());
+  output
+    .diagnostics
+    .iter()
+    .map(|warning| {
+      assert_eq!(warning.severity(), Severity::Warning);
+      (
+        warning.kind(),
+        warning.msg().to_owned(),
+        warning.pos().start_pos.line,
+      )
+    })
+    .collect()
+}
+
+#[test]
+fn warns_about_unread_variables() {
+  let source = "\
+let a: float = 1
+let b: float
+let _c: float = 2
+let d: float = {
+  let e: float = a
+  e * 2
+}
+b = 3
+d
+";
+  assert_eq!(
+    warnings(source),
+    [(
+      ErrorKind::UnusedVariable,
+      r#"variable "b" is never read"#.to_owned(),
+      1
+    )]
+  );
+}
+
+#[test]
+fn warns_about_overwritten_values() {
+  let source = "\
+let mut a: float
+let mut _b: float = 1
+a = 2
+_b = a
+a = a + 1
+a = 4
+_b = 5
+a + _b
+";
+  assert_eq!(
+    warnings(source),
+    [(
+      ErrorKind::UnusedAssignment,
+      r#"value assigned to "a" is overwritten before being read"#.to_owned(),
+      4
+    )]
+  );
+  assert_eq!(
+    warnings("let mut a: float = 1\na = 2\n{\n  a = 3\n}\na"),
+    [
+      (
+        ErrorKind::UnusedAssignment,
+        r#"value assigned to "a" is overwritten before being read"#.to_owned(),
+        0
+      ),
+      (
+        ErrorKind::UnusedAssignment,
+        r#"value assigned to "a" is overwritten before being read"#.to_owned(),
+        1
+      ),
+    ]
+  );
+}
@@ -0,0 +1,60 @@
+use caravel::ast::*;
+use caravel::backend::print::print;
+use caravel::visit::*;
+use caravel::{Lexer, Parser};
+
+fn parse(source: &str) -> Ast {
+  Parser::new(&Lexer::new(source).lex().unwrap())
+    .parse()
+    .unwrap()
+}
+
+/// Names of the variables read, in evaluation order
+struct Reads(Vec<String>);
+
+impl Visitor for Reads {
+  fn visit_leaf(&mut self, ast: &Ast, leaf: NodeId<Leaf>) {
+    match &ast[leaf] {
+      Leaf::Identifier(name) => self.0.push(name.clone()),
+      _ => walk_leaf(self, ast, leaf),
+    }
+  }
+}
+
+#[test]
+fn visits_nodes_in_evaluation_order() {
+  let ast = parse("let a: float = b * (c - d)\n{\n  a = e % a\n}\nf");
+  let mut reads = Reads(Vec::new());
+  reads.visit_block(&ast, ast.root());
+  assert_eq!(reads.0, ["b", "c", "d", "e", "a", "f"]);
+}
+
+/// Replaces every variable with zero
+struct Zero;
+
+impl VisitorMut for Zero {
+  fn visit_leaf(&mut self, ast: &mut Ast, leaf: NodeId<Leaf>) {
+    match ast[leaf] {
+      Leaf::Identifier(_) => ast[leaf] = Leaf::FloatLiteral("0".to_owned()),
+      _ => walk_leaf_mut(self, ast, leaf),
+    }
+  }
+}
+
+#[test]
+fn rewrites_nodes_in_place() {
+  let mut ast = parse("a + (1 - b)");
+  let root = ast.root();
+  Zero.visit_block(&mut ast, root);
+  assert_eq!(
+    print(&ast, None, false),
+    "\
+`- Block
+   `- Add
+      |- 0
+      `- Subtract
+         |- 1
+         `- 0
+"
+  );
+}
@@ -0,0 +1,120 @@
+use caravel::backend::wasm::emit;
+use caravel::bytecode::{self, vm};
+use caravel::ir::*;
+use caravel::{compile, CompileOptions, Emit, Type};
+use wasmi::{Engine, Linker, Module, Store};
+
+fn emit_wat(source: &str) -> String {
+  let options = CompileOptions {
+    emit: Emit::Wat,
+    ..CompileOptions::default()
+  };
+  let output = compile(source, &options);
+  assert!(output.succeeded(), "{:?}", output.diagnostics);
+  output.output.unwrap()
+}
+
+/// Assembles and validates a module, then runs its `main`
+fn run<R: wasmi::WasmResults>(wat: &str) -> R {
+  let bytes = wat::parse_str(wat).unwrap();
+  wasmparser::Validator::new().validate_all(&bytes).unwrap();
+
+  let engine = Engine::default();
+  let module = Module::new(&engine, &bytes[..]).unwrap();
+  let mut store = Store::new(&engine, ());
+  let instance = Linker::<()>::new(&engine)
+    .instantiate(&mut store, &module)
+    .unwrap()
+    .start(&mut store)
+    .unwrap();
+  let main = instance.get_typed_func::<(), R>(&store, "main").unwrap();
+  main.call(&mut store, ()).unwrap()
+}
+
+#[test]
+fn emits_valid_modules() {
+  let source = "\
+let mut x: float = 1 + 1
+let v: void = {}
+{
+  let y: float = x * 3
+  x = y - x
+}
+{
+  let w: void = v
+}
+let é: float = x / 8
+x % 3 + é
+";
+  let wat = emit_wat(source);
+  assert!(wat.contains("(local $x f64)"));
+  assert!(wat.contains("(local $y f64)"));
+  assert!(wat.contains("(local $var.4 f64)"));
+  assert_eq!(run::<f64>(&wat), 1.5);
+
+  run::<()>(&emit_wat("let v: void = {}\nv"));
+  run::<()>(&emit_wat(""));
+}
+
+#[test]
+fn agrees_with_the_vm_on_remainders() {
+  let values = [
+    "0",
+    "0.5",
+    "1 / 3",
+    "3",
+    "7.25",
+    "0 - 5",
+    "0 - 0",
+    "123456789012345678901234567890",
+    "0 - 1 / 123456789012345678901234567890",
+    "1 / 0",
+    "0 - 1 / 0",
+    "0 / 0",
+  ];
+  for x in &values {
+    for y in &values {
+      let source = format!("let mut x: float = {}\nlet mut y: float = {}\nx % y", x, y);
+      let output = compile(&source, &CompileOptions::default());
+      let program = bytecode::compile::compile(&output.ast.unwrap(), &output.resolution.unwrap());
+      let expected = vm::run(&program).unwrap().unwrap();
+
+      let actual = run::<f64>(&emit_wat(&source));
+      assert!(
+        actual.to_bits() == expected.to_bits() || actual.is_nan() && expected.is_nan(),
+        "{} % {}: {} != {}",
+        x,
+        y,
+        actual,
+        expected
+      );
+    }
+  }
+}
+
+#[test]
+fn dispatches_jumps_between_blocks() {
+  let function = Function {
+    ret: Type::Float,
+    registers: vec![Type::Float, Type::Float],
+    variables: vec![Variable {
+      name: "bb".to_owned(),
+      typ: Type::Float,
+    }],
+    blocks: vec![
+      BasicBlock {
+        instructions: vec![Instruction::Const(Reg(0), 4.0)],
+        terminator: Terminator::Jump(BlockId(2)),
+      },
+      BasicBlock {
+        instructions: vec![Instruction::Load(Reg(1), Var(0))],
+        terminator: Terminator::Return(Some(Reg(1))),
+      },
+      BasicBlock {
+        instructions: vec![Instruction::Store(Var(0), Reg(0))],
+        terminator: Terminator::Jump(BlockId(1)),
+      },
+    ],
+  };
+  assert_eq!(run::<f64>(&emit(&function)), 4.0);
+}
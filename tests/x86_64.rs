@@ -0,0 +1,71 @@
+#![cfg(all(target_os = "linux", target_arch = "x86_64"))]
+
+mod common;
+
+use caravel::Emit;
+use common::Native;
+
+const ASM: Native = Native {
+  emit: Emit::Asm,
+  extension: "s",
+  flags: &[],
+  libs: &[],
+};
+
+#[test]
+fn runs_programs() {
+  ASM.assert_agrees_with_the_vm(
+    "runs_programs",
+    &[
+      "let mut x: float = 1 + 1\n{\n  let y: float = x * 3\n  x = y - x\n}\nx % 3 + 0.5",
+      "let mut a: float = 1\nlet b: float = {\n  let c: float = 10\n  c / 3\n}\na = a - b\n(a - 1) * (a + b) - (2 - a) / (b - a)",
+      "let mut x: float = 4\nx",
+      "let v: void = {}\nv",
+      "",
+    ],
+  );
+}
+
+#[test]
+fn computes_exact_remainders() {
+  let values = [
+    "0.5",
+    "1 / 3",
+    "0 - 7.25",
+    "0 - 0",
+    "123456789012345678901234567890",
+    "1 / 0",
+    "0 / 0",
+  ];
+  let mut sources = Vec::new();
+  for x in &values {
+    for y in &values {
+      sources.push(format!(
+        "let mut x: float = {}\nlet mut y: float = {}\nx % y",
+        x, y
+      ));
+    }
+  }
+  ASM.assert_agrees_with_the_vm("computes_exact_remainders", &sources);
+}
+
+#[test]
+fn spills_when_registers_run_out() {
+  // Every level keeps a product live while the next one is computed
+  let mut expr = "x".to_owned();
+  for i in 1..=20 {
+    expr = format!("x * {} - (x / {} + {})", i, i, expr);
+  }
+  let source = format!("let mut x: float = 3\n{}", expr);
+
+  let asm = ASM.emit(&source);
+  assert!(asm.contains("%xmm13"));
+  assert!(asm.contains("-32(%rbp)"));
+  ASM.assert_agrees_with_the_vm("spills_when_registers_run_out", &[source]);
+}
+
+#[test]
+fn shares_constants_between_equal_literals() {
+  let asm = ASM.emit("let mut a: float = 1\na = a * 2 + 1.0\na / 2.00");
+  assert_eq!(asm.matches(".quad").count(), 2, "{}", asm);
+}